@@ -0,0 +1,665 @@
+// 音量エンベロープ (チャンネル1, 2, 4 共通, NRx2)
+// bit7-4: 初期音量, bit3: 方向 (1 = 増加), bit2-0: 周期
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Envelope {
+    pub initial: u8,        // 初期音量 (0-15)
+    pub direction: bool,    // true = 増加, false = 減少
+    pub period: u8,         // フレームシーケンサのtick数 (0 = 停止)
+    pub current_volume: u8, // 現在の音量
+    pub timer: u8,          // 次の音量変化までのtick数
+}
+
+impl Envelope {
+    // NRx2 への書き込み
+    pub fn write_nrx2(&mut self, value: u8) {
+        self.initial = value >> 4;
+        self.direction = value & 0x08 != 0;
+        self.period = value & 0x07;
+    }
+
+    pub fn read_nrx2(&self) -> u8 {
+        (self.initial << 4) | ((self.direction as u8) << 3) | self.period
+    }
+
+    // チャンネルのトリガー時に音量とタイマーを再設定
+    pub fn trigger(&mut self) {
+        self.current_volume = self.initial;
+        self.timer = self.period;
+    }
+
+    // フレームシーケンサのエンベロープステップ (64Hz) ごとに呼ぶ
+    pub fn tick(&mut self) {
+        if self.period == 0 {
+            return;
+        }
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer == 0 {
+            self.timer = self.period;
+            if self.direction && self.current_volume < 15 {
+                self.current_volume += 1;
+            } else if !self.direction && self.current_volume > 0 {
+                self.current_volume -= 1;
+            }
+        }
+    }
+
+    pub fn volume(&self) -> u8 {
+        self.current_volume
+    }
+
+    // NRx2 の上位5bitがすべて0なら DAC はオフ
+    pub fn dac_enabled(&self) -> bool {
+        self.initial != 0 || self.direction
+    }
+}
+
+// チャンネル1の周波数スイープ (NR10)
+// bit6-4: 周期, bit3: 方向 (1 = 減少), bit2-0: シフト量
+// 各メソッドはチャンネルを止めるべきとき true を返す
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sweep {
+    pub period: u8,
+    pub negate: bool,
+    pub shift: u8,
+    pub timer: u8,
+    pub shadow_freq: u16, // トリガー時の周波数のコピー
+    pub enabled: bool,
+    pub negate_used: bool, // トリガー後に減少モードで計算したか
+    pub is_cgb: bool,
+}
+
+impl Sweep {
+    // NR10 への書き込み
+    // DMGでは減少モードで計算した後に減少モードを解除するとチャンネルが止まる
+    pub fn write_nr10(&mut self, value: u8) -> bool {
+        let was_negate = self.negate;
+        self.period = (value >> 4) & 0x07;
+        self.negate = value & 0x08 != 0;
+        self.shift = value & 0x07;
+        !self.is_cgb && was_negate && !self.negate && self.negate_used
+    }
+
+    pub fn read_nr10(&self) -> u8 {
+        0x80 | (self.period << 4) | ((self.negate as u8) << 3) | self.shift
+    }
+
+    // チャンネルのトリガー時に呼ぶ (freq: NR13/NR14 の11bit周波数)
+    pub fn trigger(&mut self, freq: u16) -> bool {
+        self.shadow_freq = freq;
+        self.timer = self.reload_value();
+        self.enabled = self.period != 0 || self.shift != 0;
+        self.negate_used = false;
+        // シフト量が0でなければすぐにオーバーフローを確認する
+        self.shift != 0 && self.calculate() > 0x7FF
+    }
+
+    // フレームシーケンサのスイープステップ (128Hz) ごとに呼ぶ
+    // 新しい周波数は shadow_freq に入る
+    pub fn tick(&mut self) -> bool {
+        if self.timer > 0 {
+            self.timer -= 1;
+        }
+        if self.timer != 0 {
+            return false;
+        }
+        self.timer = self.reload_value();
+        if !self.enabled || self.period == 0 {
+            return false;
+        }
+        // シフト量が0でもオーバーフローの確認は行う
+        let new_freq = self.calculate();
+        if new_freq > 0x7FF {
+            return true;
+        }
+        if self.shift != 0 {
+            self.shadow_freq = new_freq;
+            // 書き戻した後にもう一度確認する
+            return self.calculate() > 0x7FF;
+        }
+        false
+    }
+
+    pub fn frequency(&self) -> u16 {
+        self.shadow_freq
+    }
+
+    // freq ± freq >> shift
+    fn calculate(&mut self) -> u16 {
+        let delta = self.shadow_freq >> self.shift;
+        if self.negate {
+            self.negate_used = true;
+            self.shadow_freq - delta
+        } else {
+            self.shadow_freq + delta
+        }
+    }
+
+    // 周期0はタイマー上は8として扱う
+    fn reload_value(&self) -> u8 {
+        if self.period == 0 { 8 } else { self.period }
+    }
+}
+
+// チャンネル1 (スイープ付き矩形波, NR10 - NR14)
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Channel1 {
+    pub enabled: bool,
+    pub sweep: Sweep,
+    pub length: LengthCounter,
+    pub envelope: Envelope,
+    pub duty: u8,        // NR11 bit7-6
+    pub frequency: u16,  // NR13 + NR14 bit2-0 (11bit)
+    pub freq_timer: u16, // 次のデューティステップまでのTサイクル数
+}
+
+impl Channel1 {
+    pub fn write_nr10(&mut self, value: u8) {
+        if self.sweep.write_nr10(value) {
+            self.enabled = false;
+        }
+    }
+
+    pub fn write_nr11(&mut self, value: u8) {
+        self.duty = value >> 6;
+        self.length.load(64, value & 0x3F);
+    }
+
+    pub fn write_nr12(&mut self, value: u8) {
+        self.envelope.write_nrx2(value);
+        if !self.envelope.dac_enabled() {
+            self.enabled = false;
+        }
+    }
+
+    pub fn write_nr13(&mut self, value: u8) {
+        self.frequency = (self.frequency & 0x0700) | value as u16;
+    }
+
+    // bit7: トリガー, bit6: 長さカウンタ有効, bit2-0: 周波数上位
+    pub fn write_nr14(&mut self, value: u8) {
+        self.frequency = (self.frequency & 0x00FF) | ((value as u16 & 0x07) << 8);
+        self.length.enabled = value & 0x40 != 0;
+        if value & 0x80 != 0 {
+            self.trigger();
+        }
+    }
+
+    // チャンネルの再スタート
+    pub fn trigger(&mut self) {
+        self.enabled = true;
+        self.length.trigger(64);
+        self.freq_timer = (2048 - self.frequency) * 4;
+        self.envelope.trigger();
+        // シフト量が0でなければここでオーバーフローを確認する
+        if self.sweep.trigger(self.frequency) {
+            self.enabled = false;
+        }
+        if !self.envelope.dac_enabled() {
+            self.enabled = false;
+        }
+    }
+}
+
+// チャンネル4のノイズ生成 (NR43)
+// bit7-4: クロックシフト s, bit3: 7bitモード, bit2-0: 分周コード r
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Lfsr {
+    pub state: u16, // 15bit
+    pub clock_shift: u8,
+    pub width_7bit: bool,
+    pub divisor_code: u8,
+}
+
+impl Lfsr {
+    pub fn write_nr43(&mut self, value: u8) {
+        self.clock_shift = value >> 4;
+        self.width_7bit = value & 0x08 != 0;
+        self.divisor_code = value & 0x07;
+    }
+
+    pub fn read_nr43(&self) -> u8 {
+        (self.clock_shift << 4) | ((self.width_7bit as u8) << 3) | self.divisor_code
+    }
+
+    // LFSRの更新間隔 (Tサイクル): 分周値 (r=0 は 8, それ以外は r*16) << s
+    pub fn period_cycles(&self) -> u32 {
+        let divisor = match self.divisor_code {
+            0 => 8,
+            r => r as u32 * 16,
+        };
+        divisor << self.clock_shift
+    }
+
+    // LFSRの更新頻度 (Hz) = 262144 / (max(r, 0.5) * 2^s)
+    pub fn clock_hz(&self) -> u32 {
+        4_194_304 / self.period_cycles()
+    }
+
+    pub fn trigger(&mut self) {
+        self.state = 0x7FFF;
+    }
+
+    // bit0 と bit1 の XOR を bit14 (7bitモードでは bit6 にも) に入れて右シフト
+    pub fn step(&mut self) {
+        let xor = (self.state ^ (self.state >> 1)) & 0x01;
+        self.state = (self.state >> 1) | (xor << 14);
+        if self.width_7bit {
+            self.state = (self.state & !0x40) | (xor << 6);
+        }
+    }
+
+    // bit0 が0のとき出力は1
+    pub fn output(&self) -> u8 {
+        (!self.state & 0x01) as u8
+    }
+}
+
+// チャンネル3の DAC は NR30 bit7 で切り替える
+pub fn wave_dac_enabled(nr30: u8) -> bool {
+    nr30 & 0x80 != 0
+}
+
+// 長さカウンタ (全チャンネル共通)
+// チャンネル1, 2, 4 は最大64, チャンネル3 は最大256
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct LengthCounter {
+    pub counter: u16,
+    pub enabled: bool, // NRx4 bit6
+}
+
+impl LengthCounter {
+    // NRx1 の長さデータを書き込む (counter = max - data)
+    pub fn load(&mut self, max: u16, length_data: u8) {
+        self.counter = max - (length_data as u16 & (max - 1));
+    }
+
+    // トリガー時、カウンタが0なら最大値に戻す
+    pub fn trigger(&mut self, max: u16) {
+        if self.counter == 0 {
+            self.counter = max;
+        }
+    }
+
+    // フレームシーケンサの長さステップ (256Hz) ごとに呼ぶ
+    // このtickで0になった (チャンネルを止める) とき true を返す
+    pub fn tick(&mut self) -> bool {
+        if !self.enabled || self.counter == 0 {
+            return false;
+        }
+        self.counter -= 1;
+        self.counter == 0
+    }
+}
+
+// チャンネル3の波形RAM (0xFF30 - 0xFF3F, 4bitサンプル x 32)
+// DMGでは再生中のCPUアクセスは再生位置のバイトに限られる
+#[derive(Debug, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WaveRam {
+    pub data: [u8; 16],
+    pub position: usize, // 再生中のサンプル番号 (0-31)
+    pub channel_enabled: bool,
+    pub is_cgb: bool,
+}
+
+impl WaveRam {
+    pub fn read(&self, addr: u16) -> u8 {
+        self.data[self.access_index(addr)]
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        let index = self.access_index(addr);
+        self.data[index] = value;
+    }
+
+    // 再生位置を1サンプル進める
+    pub fn advance(&mut self) {
+        self.position = (self.position + 1) % 32;
+    }
+
+    // 現在のサンプル (上位4bitが先)
+    pub fn sample(&self) -> u8 {
+        let byte = self.data[self.position / 2];
+        if self.position & 0x01 == 0 {
+            byte >> 4
+        } else {
+            byte & 0x0F
+        }
+    }
+
+    fn access_index(&self, addr: u16) -> usize {
+        if self.channel_enabled && !self.is_cgb {
+            self.position / 2 // DMG再生中は再生位置のバイト
+        } else {
+            (addr & 0x0F) as usize
+        }
+    }
+}
+
+// APUのレジスタ (MMU が 0xFF10 - 0xFF3F を振り分ける)
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct APU {
+    power: bool,            // NR52 bit7
+    pub channel1: Channel1, // NR10 - NR14
+    pub wave: WaveRam,      // チャンネル3の波形RAM
+}
+
+impl APU {
+    pub fn new(is_cgb: bool) -> Self {
+        Self {
+            power: true, // ブート後は電源オン
+            channel1: Channel1 {
+                sweep: Sweep {
+                    is_cgb,
+                    ..Default::default()
+                },
+                ..Default::default()
+            },
+            wave: WaveRam {
+                is_cgb,
+                ..Default::default()
+            },
+        }
+    }
+
+    // 未使用bitと書き込み専用レジスタは 1 で読める
+    pub fn read(&self, addr: u16) -> u8 {
+        let ch1 = &self.channel1;
+        match addr {
+            0xFF10 => ch1.sweep.read_nr10(),
+            0xFF11 => (ch1.duty << 6) | 0x3F,
+            0xFF12 => ch1.envelope.read_nrx2(),
+            0xFF14 => 0xBF | ((ch1.length.enabled as u8) << 6),
+            // NR52: bit7 電源, bit3-0 各チャンネルの動作状態 (今はチャンネル1のみ)
+            0xFF26 => ((self.power as u8) << 7) | 0x70 | ch1.enabled as u8,
+            0xFF30..=0xFF3F => self.wave.read(addr),
+            _ => 0xFF,
+        }
+    }
+
+    pub fn write(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF10 => self.channel1.write_nr10(value),
+            0xFF11 => self.channel1.write_nr11(value),
+            0xFF12 => self.channel1.write_nr12(value),
+            0xFF13 => self.channel1.write_nr13(value),
+            0xFF14 => self.channel1.write_nr14(value), // bit7 でトリガー
+            0xFF26 => self.power = value & 0x80 != 0,  // bit3-0 は読み出し専用
+            0xFF30..=0xFF3F => self.wave.write(addr, value),
+            _ => {}
+        }
+    }
+}
+
+impl Default for APU {
+    fn default() -> Self {
+        Self::new(false)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn envelope_trigger_reloads_volume_and_period() {
+        let mut env = Envelope::default();
+        env.write_nrx2(0xA3); // 初期音量 10, 減少, 周期 3
+        assert_eq!(env.read_nrx2(), 0xA3);
+        env.trigger();
+        assert_eq!((env.volume(), env.timer), (10, 3));
+    }
+
+    #[test]
+    fn envelope_steps_and_clamps() {
+        let mut env = Envelope::default();
+        env.write_nrx2(0xE9); // 初期音量 14, 増加, 周期 1
+        env.trigger();
+        env.tick();
+        assert_eq!(env.volume(), 15);
+        env.tick();
+        assert_eq!(env.volume(), 15); // 15 で止まる
+
+        env.write_nrx2(0x12); // 初期音量 1, 減少, 周期 2
+        env.trigger();
+        env.tick();
+        assert_eq!(env.volume(), 1); // 2tickごとに変化
+        env.tick();
+        assert_eq!(env.volume(), 0);
+        env.tick();
+        env.tick();
+        assert_eq!(env.volume(), 0); // 0 で止まる
+    }
+
+    #[test]
+    fn length_counter_load_and_trigger() {
+        let mut length = LengthCounter::default();
+        length.load(64, 0x3F);
+        assert_eq!(length.counter, 1);
+        length.load(256, 0x00);
+        assert_eq!(length.counter, 256);
+        length.load(64, 0xC5); // 上位bitは無視
+        assert_eq!(length.counter, 59);
+
+        length.trigger(64);
+        assert_eq!(length.counter, 59); // 0でなければそのまま
+        length.counter = 0;
+        length.trigger(64);
+        assert_eq!(length.counter, 64);
+    }
+
+    #[test]
+    fn length_counter_disables_only_when_enabled() {
+        let mut length = LengthCounter::default();
+        length.load(64, 0x3E); // counter = 2
+        assert!(!length.tick());
+        assert_eq!(length.counter, 2); // 無効の間は減らない
+
+        length.enabled = true;
+        assert!(!length.tick());
+        assert!(length.tick()); // 0 になったtickだけ true
+        assert!(!length.tick());
+        assert_eq!(length.counter, 0);
+    }
+
+    #[test]
+    fn dac_enable_bits() {
+        assert!(wave_dac_enabled(0x80));
+        assert!(!wave_dac_enabled(0x7F));
+
+        let mut env = Envelope::default();
+        env.write_nrx2(0x07);
+        assert!(!env.dac_enabled()); // 上位5bitがすべて0
+        env.write_nrx2(0x08);
+        assert!(env.dac_enabled());
+        env.write_nrx2(0x10);
+        assert!(env.dac_enabled());
+    }
+
+    #[test]
+    fn envelope_period_zero_holds_volume() {
+        let mut env = Envelope::default();
+        env.write_nrx2(0x78); // 初期音量 7, 増加, 周期 0
+        env.trigger();
+        for _ in 0..16 {
+            env.tick();
+        }
+        assert_eq!(env.volume(), 7);
+    }
+
+    #[test]
+    fn nr52_power_bit_and_status_bits() {
+        let mut apu = APU::new(false);
+        assert_eq!(apu.read(0xFF26), 0xF0);
+        apu.write(0xFF26, 0x0F); // 電源オフ (下位bitは書けない)
+        assert_eq!(apu.read(0xFF26), 0x70);
+        apu.write(0xFF26, 0x80);
+        assert_eq!(apu.read(0xFF26), 0xF0);
+    }
+
+    #[test]
+    fn sweep_increases_frequency() {
+        let mut sweep = Sweep::default();
+        sweep.write_nr10(0x11); // 周期1, 増加, シフト1
+        assert!(!sweep.trigger(0x100));
+        assert!(!sweep.tick());
+        assert_eq!(sweep.frequency(), 0x180);
+    }
+
+    #[test]
+    fn sweep_overflow_disables_channel() {
+        let mut sweep = Sweep::default();
+        sweep.write_nr10(0x11);
+        assert!(sweep.trigger(0x600)); // 0x600 + 0x300 > 0x7FF
+
+        // シフト量0ではトリガー時に確認しないが, tick では確認する
+        sweep.write_nr10(0x10);
+        assert!(!sweep.trigger(0x500));
+        assert!(sweep.tick()); // 0x500 + 0x500 > 0x7FF
+        assert_eq!(sweep.frequency(), 0x500);
+    }
+
+    #[test]
+    fn sweep_negate_decreases_frequency() {
+        let mut sweep = Sweep::default();
+        sweep.write_nr10(0x19); // 周期1, 減少, シフト1
+        assert!(!sweep.trigger(0x100));
+        assert!(!sweep.tick());
+        assert_eq!(sweep.frequency(), 0x080);
+    }
+
+    #[test]
+    fn sweep_clearing_negate_after_use_disables_on_dmg_only() {
+        for (is_cgb, disabled) in [(false, true), (true, false)] {
+            let mut sweep = Sweep {
+                is_cgb,
+                ..Default::default()
+            };
+            sweep.write_nr10(0x19);
+            sweep.trigger(0x100);
+            sweep.tick();
+            assert_eq!(sweep.write_nr10(0x11), disabled);
+        }
+
+        // 減少モードで計算していなければ止まらない
+        let mut sweep = Sweep::default();
+        sweep.write_nr10(0x08);
+        sweep.trigger(0x100);
+        assert!(!sweep.write_nr10(0x00));
+    }
+
+    #[test]
+    fn channel1_trigger_reloads_units() {
+        let mut ch1 = Channel1::default();
+        ch1.write_nr12(0xF3); // 音量15, 減少, 周期3
+        ch1.write_nr13(0x00);
+        ch1.write_nr14(0x87); // トリガー, 周波数 0x700
+        assert!(ch1.enabled);
+        assert_eq!(ch1.length.counter, 64); // 0 なら最大値に戻す
+        assert_eq!(ch1.envelope.volume(), 15);
+        assert_eq!(ch1.envelope.timer, 3);
+        assert_eq!(ch1.freq_timer, (2048 - 0x700) * 4);
+        assert_eq!(ch1.sweep.frequency(), 0x700);
+    }
+
+    #[test]
+    fn channel1_trigger_disables_on_sweep_overflow_or_dac_off() {
+        let mut ch1 = Channel1::default();
+        ch1.write_nr10(0x11); // シフト1
+        ch1.write_nr12(0xF0);
+        ch1.write_nr14(0x87); // 0x700 + 0x380 > 0x7FF
+        assert!(!ch1.enabled);
+
+        let mut ch1 = Channel1::default();
+        ch1.write_nr12(0x00); // DAC オフ
+        ch1.write_nr14(0x80);
+        assert!(!ch1.enabled);
+    }
+
+    #[test]
+    fn apu_register_reads_mask_unused_bits() {
+        let mut apu = APU::new(false);
+        apu.write(0xFF10, 0x3A);
+        apu.write(0xFF11, 0x80);
+        apu.write(0xFF12, 0xF3);
+        apu.write(0xFF13, 0x12);
+        apu.write(0xFF14, 0x40);
+        assert_eq!(apu.read(0xFF10), 0xBA);
+        assert_eq!(apu.read(0xFF11), 0xBF); // 長さデータは書き込み専用
+        assert_eq!(apu.read(0xFF12), 0xF3);
+        assert_eq!(apu.read(0xFF13), 0xFF); // 書き込み専用
+        assert_eq!(apu.read(0xFF14), 0xFF);
+        assert_eq!(apu.channel1.frequency, 0x012);
+    }
+
+    #[test]
+    fn nr52_bit0_reports_channel1() {
+        let mut apu = APU::new(false);
+        assert_eq!(apu.read(0xFF26), 0xF0);
+        apu.write(0xFF12, 0xF0); // DAC オン
+        apu.write(0xFF14, 0x80);
+        assert_eq!(apu.read(0xFF26), 0xF1);
+        apu.write(0xFF12, 0x00); // DAC オフで止まる
+        assert_eq!(apu.read(0xFF26), 0xF0);
+    }
+
+    #[test]
+    fn nr43_clock_rate() {
+        let mut lfsr = Lfsr::default();
+        for (nr43, hz) in [
+            (0x00, 524_288), // r=0, s=0
+            (0x01, 262_144), // r=1, s=0
+            (0x10, 262_144), // r=0, s=1
+            (0x07, 37_449),  // r=7, s=0 (262144 / 7)
+            (0x23, 21_845),  // r=3, s=2 (262144 / 12)
+            (0xD0, 64),      // r=0, s=13
+        ] {
+            lfsr.write_nr43(nr43);
+            assert_eq!(lfsr.clock_hz(), hz, "NR43={:02X}", nr43);
+            assert_eq!(lfsr.read_nr43(), nr43);
+        }
+    }
+
+    #[test]
+    fn lfsr_7bit_mode_copies_feedback_to_bit6() {
+        let mut lfsr = Lfsr::default();
+        lfsr.write_nr43(0x08);
+        lfsr.trigger();
+        lfsr.state = 0x0001; // bit0=1, bit1=0 -> xor=1
+        lfsr.step();
+        assert_eq!(lfsr.state, 0x4040);
+        assert_eq!(lfsr.output(), 1);
+    }
+
+    #[test]
+    fn wave_ram_dmg_playback_reads_current_byte() {
+        let mut wave = WaveRam::default();
+        for i in 0..16 {
+            wave.write(0xFF30 + i, i as u8 * 0x11);
+        }
+        wave.channel_enabled = true;
+        wave.position = 10; // 5バイト目
+        assert_eq!(wave.read(0xFF30), 0x55);
+        assert_eq!(wave.read(0xFF3F), 0x55);
+    }
+
+    #[test]
+    fn wave_ram_cgb_playback_reads_address() {
+        let mut wave = WaveRam {
+            is_cgb: true,
+            ..Default::default()
+        };
+        wave.write(0xFF3F, 0xAB);
+        wave.channel_enabled = true;
+        wave.position = 10;
+        assert_eq!(wave.read(0xFF3F), 0xAB);
+    }
+}
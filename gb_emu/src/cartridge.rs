@@ -0,0 +1,742 @@
+use crate::model::GameBoyModel;
+use std::fmt;
+
+#[derive(Debug, PartialEq)]
+pub enum CartridgeError {
+    UnknownType(u8),   // 0x0147 が未定義の値
+    HeaderTooShort,    // ROMがヘッダ (0x0100 - 0x014F) を含まない
+    BadHeaderChecksum, // 0x014D のヘッダチェックサム不一致
+}
+
+impl fmt::Display for CartridgeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CartridgeError::UnknownType(code) => {
+                write!(f, "unknown cartridge type: 0x{:02X}", code)
+            }
+            CartridgeError::HeaderTooShort => write!(f, "ROM too short for a cartridge header"),
+            CartridgeError::BadHeaderChecksum => write!(f, "bad header checksum"),
+        }
+    }
+}
+
+impl std::error::Error for CartridgeError {}
+
+// ROMデータ (バンク単位でアクセスする)
+// bank * bank_size + offset は u16 に収まらないため usize で計算する
+pub struct Rom {
+    data: Vec<u8>,
+    bank_size: u16,
+}
+
+impl Rom {
+    pub fn new(data: Vec<u8>) -> Self {
+        Self {
+            data,
+            bank_size: 0x4000, // 16KB
+        }
+    }
+
+    // 0x0000 - 0x3FFF (バンク0)
+    pub fn read_bank0(&self, offset: u16) -> u8 {
+        self.read_bank_n(0, offset)
+    }
+
+    // 指定バンクの offset を読む (ROM外は 0xFF)
+    pub fn read_bank_n(&self, bank: u16, offset: u16) -> u8 {
+        let addr = bank as usize * self.bank_size as usize + offset as usize;
+        self.data.get(addr).copied().unwrap_or(0xFF)
+    }
+}
+
+impl Default for Rom {
+    fn default() -> Self {
+        Self::new(Vec::new())
+    }
+}
+
+// カートリッジの種類 (ヘッダ 0x0147)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CartridgeType {
+    RomOnly,                    // 0x00
+    Mbc1,                       // 0x01
+    Mbc1Ram,                    // 0x02
+    Mbc1RamBattery,             // 0x03
+    Mbc2,                       // 0x05
+    Mbc2Battery,                // 0x06
+    RomRam,                     // 0x08
+    RomRamBattery,              // 0x09
+    Mmm01,                      // 0x0B
+    Mmm01Ram,                   // 0x0C
+    Mmm01RamBattery,            // 0x0D
+    Mbc3TimerBattery,           // 0x0F
+    Mbc3TimerRamBattery,        // 0x10
+    Mbc3,                       // 0x11
+    Mbc3Ram,                    // 0x12
+    Mbc3RamBattery,             // 0x13
+    Mbc5,                       // 0x19
+    Mbc5Ram,                    // 0x1A
+    Mbc5RamBattery,             // 0x1B
+    Mbc5Rumble,                 // 0x1C
+    Mbc5RumbleRam,              // 0x1D
+    Mbc5RumbleRamBattery,       // 0x1E
+    Mbc6,                       // 0x20
+    Mbc7SensorRumbleRamBattery, // 0x22
+    PocketCamera,               // 0xFC
+    BandaiTama5,                // 0xFD
+    HuC3,                       // 0xFE
+    HuC1RamBattery,             // 0xFF
+}
+
+impl TryFrom<u8> for CartridgeType {
+    type Error = CartridgeError;
+
+    fn try_from(code: u8) -> Result<Self, Self::Error> {
+        let cartridge_type = match code {
+            0x00 => CartridgeType::RomOnly,
+            0x01 => CartridgeType::Mbc1,
+            0x02 => CartridgeType::Mbc1Ram,
+            0x03 => CartridgeType::Mbc1RamBattery,
+            0x05 => CartridgeType::Mbc2,
+            0x06 => CartridgeType::Mbc2Battery,
+            0x08 => CartridgeType::RomRam,
+            0x09 => CartridgeType::RomRamBattery,
+            0x0B => CartridgeType::Mmm01,
+            0x0C => CartridgeType::Mmm01Ram,
+            0x0D => CartridgeType::Mmm01RamBattery,
+            0x0F => CartridgeType::Mbc3TimerBattery,
+            0x10 => CartridgeType::Mbc3TimerRamBattery,
+            0x11 => CartridgeType::Mbc3,
+            0x12 => CartridgeType::Mbc3Ram,
+            0x13 => CartridgeType::Mbc3RamBattery,
+            0x19 => CartridgeType::Mbc5,
+            0x1A => CartridgeType::Mbc5Ram,
+            0x1B => CartridgeType::Mbc5RamBattery,
+            0x1C => CartridgeType::Mbc5Rumble,
+            0x1D => CartridgeType::Mbc5RumbleRam,
+            0x1E => CartridgeType::Mbc5RumbleRamBattery,
+            0x20 => CartridgeType::Mbc6,
+            0x22 => CartridgeType::Mbc7SensorRumbleRamBattery,
+            0xFC => CartridgeType::PocketCamera,
+            0xFD => CartridgeType::BandaiTama5,
+            0xFE => CartridgeType::HuC3,
+            0xFF => CartridgeType::HuC1RamBattery,
+            _ => return Err(CartridgeError::UnknownType(code)),
+        };
+        Ok(cartridge_type)
+    }
+}
+
+// カートリッジヘッダ (0x0134 - 0x014F)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CartridgeHeader {
+    pub title: String,                 // 0x0134 - 0x0143 (0 埋め, CGBフラグは含めない)
+    pub model: GameBoyModel,           // 0x0143 / 0x0146 から判定
+    pub cartridge_type: CartridgeType, // 0x0147
+    pub rom_size: u8,                  // 0x0148 (32KB << n)
+    pub ram_size: u8,                  // 0x0149
+    pub destination: u8,               // 0x014A (0x00 = 日本)
+    pub version: u8,                   // 0x014C
+    pub header_checksum: u8,           // 0x014D
+    pub global_checksum: u16,          // 0x014E - 0x014F (ビッグエンディアン)
+}
+
+impl CartridgeHeader {
+    pub fn parse(rom: &[u8]) -> Result<Self, CartridgeError> {
+        if rom.len() < 0x0150 {
+            return Err(CartridgeError::HeaderTooShort);
+        }
+        let title = rom[0x0134..=0x0143]
+            .iter()
+            .take_while(|&&b| b != 0 && b < 0x80)
+            .map(|&b| b as char)
+            .collect();
+        Ok(Self {
+            title,
+            model: GameBoyModel::from_rom(rom),
+            cartridge_type: CartridgeType::try_from(rom[0x0147])?,
+            rom_size: rom[0x0148],
+            ram_size: rom[0x0149],
+            destination: rom[0x014A],
+            version: rom[0x014C],
+            header_checksum: rom[0x014D],
+            global_checksum: u16::from_be_bytes([rom[0x014E], rom[0x014F]]),
+        })
+    }
+}
+
+// 外部RAMのサイズ (ヘッダ 0x0149) をバイト数に変換
+pub fn external_ram_size(ram_size_code: u8) -> usize {
+    match ram_size_code {
+        0x01 => 0x800,   // 2KB
+        0x02 => 0x2000,  // 8KB
+        0x03 => 0x8000,  // 32KB (8KB x 4)
+        0x04 => 0x20000, // 128KB (8KB x 16)
+        0x05 => 0x10000, // 64KB (8KB x 8)
+        _ => 0,          // 0x00: RAMなし
+    }
+}
+
+// MBC1 のバンク切り替えレジスタ
+// 0x0000-0x1FFF: 外部RAM有効化 (下位4bitが 0x0A で有効)
+// 0x2000-0x3FFF: ROMバンク番号の下位5bit
+// 0x4000-0x5FFF: RAMバンク番号 / ROMバンク番号の bit5-6 (2bit)
+// 0x6000-0x7FFF: バンキングモード
+//   モード0: 0x0000-0x3FFF はバンク0固定, RAM もバンク0固定
+//   モード1: 2bit レジスタで 0x0000-0x3FFF と RAM のバンクも切り替える
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mbc1 {
+    ram_enabled: bool,
+    rom_bank: u8,       // 下位5bit
+    secondary_bank: u8, // 2bit
+    mode: u8,           // 0 / 1
+    rom_bank_mask: u16, // ROMのバンク数 - 1 (存在しない上位bitは無視される)
+}
+
+impl Mbc1 {
+    pub fn new(rom_banks: usize) -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            secondary_bank: 0,
+            mode: 0,
+            rom_bank_mask: (rom_banks.max(2).next_power_of_two() - 1) as u16,
+        }
+    }
+
+    // 0x0000 - 0x7FFF への書き込み
+    pub fn write_byte(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x1F,
+            0x4000..=0x5FFF => self.secondary_bank = value & 0x03,
+            0x6000..=0x7FFF => self.mode = value & 0x01,
+            _ => {}
+        }
+    }
+
+    // 0x0000 - 0x3FFF に割り当てるバンク (モード1 のときだけ bit5-6 が効く)
+    pub fn rom_bank_low(&self) -> u16 {
+        if self.mode == 1 {
+            ((self.secondary_bank as u16) << 5) & self.rom_bank_mask
+        } else {
+            0
+        }
+    }
+
+    // 0x4000 - 0x7FFF に割り当てるバンク (ROM読み出しはすべてここを通る)
+    // 下位5bitが 0 のときは 1 として扱う (0x20, 0x40, 0x60 は 0x21, 0x41, 0x61 になる)
+    // bit5-6 は 2bit レジスタから取る (2MB ROM では 7bit のバンク番号になる)
+    pub fn rom_bank_effective(&self) -> u16 {
+        let bank = if self.rom_bank == 0 {
+            1
+        } else {
+            self.rom_bank as u16
+        };
+        (bank | ((self.secondary_bank as u16) << 5)) & self.rom_bank_mask
+    }
+
+    // 0xA000 - 0xBFFF に割り当てるRAMバンク (モード0 では常に0)
+    pub fn ram_bank(&self) -> u8 {
+        if self.mode == 1 {
+            self.secondary_bank
+        } else {
+            0
+        }
+    }
+
+    pub fn ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+}
+
+// MBC3 のリアルタイムクロック (0x08-0x0C で選ぶ5個のレジスタ)
+// 0x08: 秒, 0x09: 分, 0x0A: 時, 0x0B: 日の下位8bit
+// 0x0C: bit0 日の bit8, bit6 停止, bit7 日のキャリー
+// 読み出しはラッチした値, 書き込みは時計そのもの (時刻合わせ)
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Rtc {
+    registers: [u8; 5],
+    latched: [u8; 5],
+}
+
+impl Rtc {
+    // 各レジスタの有効なbit
+    const MASKS: [u8; 5] = [0x3F, 0x3F, 0x1F, 0xFF, 0xC1];
+
+    pub fn read(&self, register: u8) -> u8 {
+        self.latched[(register - 0x08) as usize]
+    }
+
+    pub fn write(&mut self, register: u8, value: u8) {
+        let index = (register - 0x08) as usize;
+        self.registers[index] = value & Self::MASKS[index];
+    }
+
+    // 現在の時計の値を読み出し用に固定する
+    pub fn latch(&mut self) {
+        self.latched = self.registers;
+    }
+}
+
+// MBC3 のバンク切り替えレジスタ
+// 0x0000-0x1FFF: 外部RAMとRTCの有効化 (下位4bitが 0x0A で有効)
+// 0x2000-0x3FFF: ROMバンク番号 (7bit, 0 は 1 として扱う)
+// 0x4000-0x5FFF: 0x00-0x03 でRAMバンク, 0x08-0x0C でRTCレジスタを 0xA000-0xBFFF に割り当てる
+// 0x6000-0x7FFF: 0x00 → 0x01 の順に書くとRTCをラッチ
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Mbc3 {
+    ram_enabled: bool,
+    rom_bank: u8,       // 7bit
+    ram_select: u8,     // RAMバンク (0x00-0x03) か RTCレジスタ (0x08-0x0C)
+    latch_armed: bool,  // 直前にラッチレジスタへ 0x00 が書かれた
+    rom_bank_mask: u16, // ROMのバンク数 - 1
+    rtc: Rtc,
+}
+
+impl Mbc3 {
+    pub fn new(rom_banks: usize) -> Self {
+        Self {
+            ram_enabled: false,
+            rom_bank: 1,
+            ram_select: 0,
+            latch_armed: false,
+            rom_bank_mask: (rom_banks.max(2).next_power_of_two() - 1) as u16,
+            rtc: Rtc::default(),
+        }
+    }
+
+    // 0x0000 - 0x7FFF への書き込み
+    pub fn write_byte(&mut self, addr: u16, value: u8) {
+        match addr {
+            0x0000..=0x1FFF => self.ram_enabled = value & 0x0F == 0x0A,
+            0x2000..=0x3FFF => self.rom_bank = value & 0x7F,
+            0x4000..=0x5FFF => {
+                // それ以外の値は無視
+                if matches!(value, 0x00..=0x03 | 0x08..=0x0C) {
+                    self.ram_select = value;
+                }
+            }
+            0x6000..=0x7FFF => {
+                if self.latch_armed && value == 0x01 {
+                    self.rtc.latch();
+                }
+                self.latch_armed = value == 0x00;
+            }
+            _ => {}
+        }
+    }
+
+    pub fn rom_bank_effective(&self) -> u16 {
+        let bank = if self.rom_bank == 0 { 1 } else { self.rom_bank };
+        bank as u16 & self.rom_bank_mask
+    }
+
+    pub fn ram_bank(&self) -> u8 {
+        if self.rtc_register().is_some() {
+            0
+        } else {
+            self.ram_select
+        }
+    }
+
+    pub fn ram_enabled(&self) -> bool {
+        self.ram_enabled
+    }
+
+    // 0xA000 - 0xBFFF に割り当てたRTCレジスタ (RAMバンク選択中は None)
+    pub fn rtc_register(&self) -> Option<u8> {
+        (0x08..=0x0C)
+            .contains(&self.ram_select)
+            .then_some(self.ram_select)
+    }
+}
+
+// カートリッジのメモリバンクコントローラ (ヘッダ 0x0147 で決まる)
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Mbc {
+    RomOnly, // 32KB ROM のみ (バンク切り替えなし)
+    Mbc1(Mbc1),
+    Mbc3(Mbc3),
+}
+
+impl Mbc {
+    // 未対応・未定義の種類は ROM のみとして扱う
+    pub fn from_rom(rom: &[u8]) -> Self {
+        let banks = rom.len() / 0x4000;
+        let code = rom.get(0x0147).copied().unwrap_or(0x00);
+        match CartridgeType::try_from(code) {
+            Ok(CartridgeType::Mbc1 | CartridgeType::Mbc1Ram | CartridgeType::Mbc1RamBattery) => {
+                Mbc::Mbc1(Mbc1::new(banks))
+            }
+            Ok(
+                CartridgeType::Mbc3TimerBattery
+                | CartridgeType::Mbc3TimerRamBattery
+                | CartridgeType::Mbc3
+                | CartridgeType::Mbc3Ram
+                | CartridgeType::Mbc3RamBattery,
+            ) => Mbc::Mbc3(Mbc3::new(banks)),
+            _ => Mbc::RomOnly,
+        }
+    }
+
+    // ROM領域への書き込み (ROMのみのカートリッジでは無視)
+    pub fn write_byte(&mut self, addr: u16, value: u8) {
+        match self {
+            Mbc::RomOnly => {}
+            Mbc::Mbc1(mbc) => mbc.write_byte(addr, value),
+            Mbc::Mbc3(mbc) => mbc.write_byte(addr, value),
+        }
+    }
+
+    pub fn rom_bank_low(&self) -> u16 {
+        match self {
+            Mbc::RomOnly => 0,
+            Mbc::Mbc1(mbc) => mbc.rom_bank_low(),
+            Mbc::Mbc3(_) => 0,
+        }
+    }
+
+    pub fn rom_bank_effective(&self) -> u16 {
+        match self {
+            Mbc::RomOnly => 1,
+            Mbc::Mbc1(mbc) => mbc.rom_bank_effective(),
+            Mbc::Mbc3(mbc) => mbc.rom_bank_effective(),
+        }
+    }
+
+    // 0xA000 - 0xBFFF に割り当てる外部RAMのバンク
+    pub fn ram_bank(&self) -> u8 {
+        match self {
+            Mbc::RomOnly => 0,
+            Mbc::Mbc1(mbc) => mbc.ram_bank(),
+            Mbc::Mbc3(mbc) => mbc.ram_bank(),
+        }
+    }
+
+    // ROMのみのカートリッジには有効化レジスタが無い
+    pub fn ram_enabled(&self) -> bool {
+        match self {
+            Mbc::RomOnly => true,
+            Mbc::Mbc1(mbc) => mbc.ram_enabled(),
+            Mbc::Mbc3(mbc) => mbc.ram_enabled(),
+        }
+    }
+
+    // 0xA000 - 0xBFFF にRTCレジスタが割り当てられていればラッチした値を返す
+    pub fn read_rtc(&self) -> Option<u8> {
+        match self {
+            Mbc::Mbc3(mbc) if mbc.ram_enabled => mbc.rtc_register().map(|r| mbc.rtc.read(r)),
+            _ => None,
+        }
+    }
+
+    // RTCレジスタが割り当てられていれば書き込んで true を返す
+    pub fn write_rtc(&mut self, value: u8) -> bool {
+        match self {
+            Mbc::Mbc3(mbc) if mbc.ram_enabled => match mbc.rtc_register() {
+                Some(register) => {
+                    mbc.rtc.write(register, value);
+                    true
+                }
+                None => false,
+            },
+            _ => false,
+        }
+    }
+}
+
+// ヘッダチェックサム (0x014D) の検証
+// 0x0134 - 0x014C の各バイトについて x = x - byte - 1 を計算した値と一致すること
+// (DMGのブートROMは不一致のROMを起動しない)
+pub fn validate_header_checksum(rom: &[u8]) -> Result<(), CartridgeError> {
+    if rom.len() < 0x0150 {
+        return Err(CartridgeError::HeaderTooShort);
+    }
+    let checksum = rom[0x0134..=0x014C]
+        .iter()
+        .fold(0u8, |acc, &b| acc.wrapping_sub(b).wrapping_sub(1));
+    if checksum == rom[0x014D] {
+        Ok(())
+    } else {
+        Err(CartridgeError::BadHeaderChecksum)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn header_parse() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0134..0x013A].copy_from_slice(b"TETRIS");
+        rom[0x0143] = 0x80; // CGB対応 (タイトルには含めない)
+        rom[0x0146] = 0x03;
+        rom[0x0147] = 0x03;
+        rom[0x0148] = 0x01;
+        rom[0x0149] = 0x02;
+        rom[0x014A] = 0x01;
+        rom[0x014C] = 0x02;
+        rom[0x014D] = 0xAB;
+        rom[0x014E..0x0150].copy_from_slice(&[0x12, 0x34]);
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        assert_eq!(header.title, "TETRIS");
+        assert_eq!(header.model, GameBoyModel::Cgb);
+        assert_eq!(header.cartridge_type, CartridgeType::Mbc1RamBattery);
+        assert_eq!((header.rom_size, header.ram_size), (0x01, 0x02));
+        assert_eq!((header.destination, header.version), (0x01, 0x02));
+        assert_eq!(
+            (header.header_checksum, header.global_checksum),
+            (0xAB, 0x1234)
+        );
+
+        rom[0x0147] = 0x04;
+        assert_eq!(
+            CartridgeHeader::parse(&rom),
+            Err(CartridgeError::UnknownType(0x04))
+        );
+        assert_eq!(
+            CartridgeHeader::parse(&rom[..0x014F]),
+            Err(CartridgeError::HeaderTooShort)
+        );
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn header_serde_round_trip() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0134..0x0138].copy_from_slice(b"GAME");
+        rom[0x0147] = 0x13;
+        let header = CartridgeHeader::parse(&rom).unwrap();
+        let json = serde_json::to_string(&header).unwrap();
+        let loaded: CartridgeHeader = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded, header);
+    }
+
+    #[test]
+    fn mbc1_mode0_banks_upper_window_only() {
+        let mut mbc = Mbc1::new(128); // 2MB
+        mbc.write_byte(0x2000, 0x05);
+        mbc.write_byte(0x4000, 0x02);
+        assert_eq!(mbc.rom_bank_low(), 0);
+        assert_eq!(mbc.rom_bank_effective(), 0x45);
+        assert_eq!(mbc.ram_bank(), 0); // モード0 ではRAMはバンク0固定
+    }
+
+    #[test]
+    fn mbc1_mode1_banks_low_window_and_ram() {
+        let mut mbc = Mbc1::new(128);
+        mbc.write_byte(0x2000, 0x05);
+        mbc.write_byte(0x4000, 0x02);
+        mbc.write_byte(0x6000, 0x01);
+        assert_eq!(mbc.rom_bank_low(), 0x40);
+        assert_eq!(mbc.rom_bank_effective(), 0x45);
+        assert_eq!(mbc.ram_bank(), 2);
+
+        mbc.write_byte(0x6000, 0x00); // モード0 に戻すと低位側はバンク0
+        assert_eq!(mbc.rom_bank_low(), 0);
+    }
+
+    #[test]
+    fn mbc1_small_rom_ignores_secondary_bits() {
+        let mut mbc = Mbc1::new(32); // 512KB: bit5-6 はROMに届かない
+        mbc.write_byte(0x2000, 0x03);
+        mbc.write_byte(0x4000, 0x01);
+        mbc.write_byte(0x6000, 0x01);
+        assert_eq!(mbc.rom_bank_low(), 0);
+        assert_eq!(mbc.rom_bank_effective(), 0x03);
+        assert_eq!(mbc.ram_bank(), 1);
+    }
+
+    #[test]
+    fn mbc1_bank0_maps_to_bank1() {
+        let mut mbc = Mbc1::new(128);
+        mbc.write_byte(0x2000, 0x00);
+        assert_eq!(mbc.rom_bank_effective(), 1);
+        mbc.write_byte(0x2000, 0xE0); // 下位5bitだけ見るので 0 と同じ
+        assert_eq!(mbc.rom_bank_effective(), 1);
+        mbc.write_byte(0x4000, 0x03);
+        assert_eq!(mbc.rom_bank_effective(), 0x61);
+    }
+
+    #[test]
+    fn mbc1_seven_bit_bank_for_2mb_rom() {
+        let mut mbc = Mbc1::new(128); // 2MB
+        mbc.write_byte(0x2000, 0x1F);
+        mbc.write_byte(0x4000, 0x03);
+        assert_eq!(mbc.rom_bank_effective(), 0x7F);
+        mbc.write_byte(0x6000, 0x01); // モード1 でも上位側は同じ
+        assert_eq!(mbc.rom_bank_effective(), 0x7F);
+    }
+
+    #[test]
+    fn mbc1_ram_enable() {
+        let mut mbc = Mbc1::new(4);
+        assert!(!mbc.ram_enabled());
+        mbc.write_byte(0x0000, 0x0A);
+        assert!(mbc.ram_enabled());
+        mbc.write_byte(0x1FFF, 0x00);
+        assert!(!mbc.ram_enabled());
+    }
+
+    #[test]
+    fn mbc3_rom_bank_uses_seven_bits() {
+        let mut mbc = Mbc3::new(128);
+        assert_eq!(mbc.rom_bank_effective(), 1);
+        mbc.write_byte(0x2000, 0x00); // 0 は 1
+        assert_eq!(mbc.rom_bank_effective(), 1);
+        mbc.write_byte(0x3FFF, 0x7F);
+        assert_eq!(mbc.rom_bank_effective(), 0x7F);
+        mbc.write_byte(0x2000, 0x20); // MBC1 と違い 0x20 もそのまま
+        assert_eq!(mbc.rom_bank_effective(), 0x20);
+    }
+
+    #[test]
+    fn mbc3_selects_ram_bank_or_rtc_register() {
+        let mut mbc = Mbc3::new(4);
+        for bank in 0x00..=0x03 {
+            mbc.write_byte(0x4000, bank);
+            assert_eq!((mbc.ram_bank(), mbc.rtc_register()), (bank, None));
+        }
+        for register in 0x08..=0x0C {
+            mbc.write_byte(0x5FFF, register);
+            assert_eq!((mbc.ram_bank(), mbc.rtc_register()), (0, Some(register)));
+        }
+        mbc.write_byte(0x4000, 0x02);
+        mbc.write_byte(0x4000, 0x05); // 未定義の値は無視
+        assert_eq!((mbc.ram_bank(), mbc.rtc_register()), (0x02, None));
+    }
+
+    #[test]
+    fn mbc3_rtc_reads_latched_value() {
+        let mut mbc = Mbc::Mbc3(Mbc3::new(4));
+        mbc.write_byte(0x4000, 0x08); // 秒
+        assert_eq!(mbc.read_rtc(), None); // 無効化中
+        assert!(!mbc.write_rtc(30));
+
+        mbc.write_byte(0x0000, 0x0A);
+        assert!(mbc.write_rtc(30));
+        assert_eq!(mbc.read_rtc(), Some(0)); // まだラッチしていない
+
+        mbc.write_byte(0x6000, 0x01); // 0x00 を挟まないとラッチしない
+        assert_eq!(mbc.read_rtc(), Some(0));
+        mbc.write_byte(0x6000, 0x00);
+        mbc.write_byte(0x6000, 0x01);
+        assert_eq!(mbc.read_rtc(), Some(30));
+
+        mbc.write_rtc(45); // ラッチし直すまで読み出しは変わらない
+        assert_eq!(mbc.read_rtc(), Some(30));
+
+        mbc.write_byte(0x4000, 0x0C); // 日の上位 + フラグ
+        mbc.write_rtc(0xFF);
+        mbc.write_byte(0x6000, 0x00);
+        mbc.write_byte(0x6000, 0x01);
+        assert_eq!(mbc.read_rtc(), Some(0xC1));
+
+        mbc.write_byte(0x4000, 0x01); // RAMバンクに戻すとRTCは見えない
+        assert_eq!(mbc.read_rtc(), None);
+        assert!(!mbc.write_rtc(0x12));
+    }
+
+    #[test]
+    fn rom_reads_high_banks_without_overflow() {
+        // 8MB (512バンク): 0x1FF * 0x4000 は u16 に収まらない
+        let mut data = vec![0x00; 0x200 * 0x4000];
+        data[0x0000] = 0x11;
+        data[0x1FF * 0x4000] = 0x22;
+        data[0x1FF * 0x4000 + 0x3FFF] = 0x33;
+        let rom = Rom::new(data);
+        assert_eq!(rom.read_bank0(0x0000), 0x11);
+        assert_eq!(rom.read_bank_n(0x1FF, 0x0000), 0x22);
+        assert_eq!(rom.read_bank_n(0x1FF, 0x3FFF), 0x33);
+        assert_eq!(rom.read_bank_n(0x200, 0x0000), 0xFF); // ROMの外
+    }
+
+    #[test]
+    fn rom_out_of_range_reads_ff() {
+        let rom = Rom::new(vec![0xAB; 0x8000]);
+        assert_eq!(rom.read_bank0(0x3FFF), 0xAB);
+        assert_eq!(rom.read_bank_n(1, 0x3FFF), 0xAB);
+        assert_eq!(rom.read_bank_n(2, 0x0000), 0xFF);
+        assert_eq!(Rom::default().read_bank0(0x0000), 0xFF);
+    }
+
+    #[test]
+    fn external_ram_size_by_code() {
+        for (code, size) in [
+            (0x00, 0),
+            (0x01, 0x800),
+            (0x02, 0x2000),
+            (0x03, 0x8000),
+            (0x04, 0x20000),
+            (0x05, 0x10000),
+            (0x06, 0), // 未定義
+        ] {
+            assert_eq!(external_ram_size(code), size, "code 0x{:02X}", code);
+        }
+    }
+
+    #[test]
+    fn cartridge_type_from_header_byte() {
+        assert_eq!(CartridgeType::try_from(0x00), Ok(CartridgeType::RomOnly));
+        assert_eq!(
+            CartridgeType::try_from(0x03),
+            Ok(CartridgeType::Mbc1RamBattery)
+        );
+        assert_eq!(
+            CartridgeType::try_from(0x13),
+            Ok(CartridgeType::Mbc3RamBattery)
+        );
+        assert_eq!(
+            CartridgeType::try_from(0x1B),
+            Ok(CartridgeType::Mbc5RamBattery)
+        );
+        assert_eq!(
+            CartridgeType::try_from(0xFF),
+            Ok(CartridgeType::HuC1RamBattery)
+        );
+        for code in [0x04, 0x07, 0x0A, 0x14, 0x21, 0xFB] {
+            assert_eq!(
+                CartridgeType::try_from(code),
+                Err(CartridgeError::UnknownType(code))
+            );
+        }
+    }
+
+    #[test]
+    fn mbc_from_cartridge_type() {
+        let mut rom = vec![0x00; 0x8000];
+        assert_eq!(Mbc::from_rom(&rom), Mbc::RomOnly);
+        rom[0x0147] = 0x03;
+        assert!(matches!(Mbc::from_rom(&rom), Mbc::Mbc1(_)));
+        rom[0x0147] = 0x10;
+        assert!(matches!(Mbc::from_rom(&rom), Mbc::Mbc3(_)));
+        rom[0x0147] = 0x04; // 未定義
+        assert_eq!(Mbc::from_rom(&rom), Mbc::RomOnly);
+    }
+
+    #[test]
+    fn header_checksum() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0134..0x013A].copy_from_slice(b"TETRIS");
+        rom[0x014D] = 0x0C; // タイトル "TETRIS" 以外は0のヘッダ
+        assert_eq!(validate_header_checksum(&rom), Ok(()));
+
+        rom[0x014D] = 0x0D;
+        assert_eq!(
+            validate_header_checksum(&rom),
+            Err(CartridgeError::BadHeaderChecksum)
+        );
+        assert_eq!(
+            validate_header_checksum(&rom[..0x0100]),
+            Err(CartridgeError::HeaderTooShort)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+// ROM領域 (0x0000 - 0x7FFF) のアドレス数
+const ROM_SIZE: usize = 0x8000;
+
+// 実行されたROMアドレスを1アドレス1ビットで記録する
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CodeCoverage {
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    visited: [u8; ROM_SIZE / 8], // 32K bits = 4KB
+}
+
+impl CodeCoverage {
+    pub fn new() -> Self {
+        Self {
+            visited: [0; ROM_SIZE / 8],
+        }
+    }
+
+    // 実行したアドレスを記録 (ROM領域外は無視)
+    pub fn visit(&mut self, addr: u16) {
+        let addr = addr as usize;
+        if addr < ROM_SIZE {
+            self.visited[addr / 8] |= 1 << (addr % 8);
+        }
+    }
+
+    pub fn is_visited(&self, addr: u16) -> bool {
+        let addr = addr as usize;
+        addr < ROM_SIZE && self.visited[addr / 8] & (1 << (addr % 8)) != 0
+    }
+
+    // 実行済みアドレスの割合 (%)
+    pub fn coverage_percent(&self) -> f64 {
+        let count: u32 = self.visited.iter().map(|b| b.count_ones()).sum();
+        count as f64 * 100.0 / ROM_SIZE as f64
+    }
+
+    // 一度も実行されていないアドレス範囲 (start, end) の一覧 (endを含む)
+    pub fn unvisited_ranges(&self) -> Vec<(u16, u16)> {
+        let mut ranges = Vec::new();
+        let mut start: Option<u16> = None;
+        for addr in 0..ROM_SIZE as u16 {
+            match (self.is_visited(addr), start) {
+                (false, None) => start = Some(addr),
+                (true, Some(s)) => {
+                    ranges.push((s, addr - 1));
+                    start = None;
+                }
+                _ => {}
+            }
+        }
+        if let Some(s) = start {
+            ranges.push((s, (ROM_SIZE - 1) as u16));
+        }
+        ranges
+    }
+
+    pub fn clear(&mut self) {
+        self.visited = [0; ROM_SIZE / 8];
+    }
+}
+
+impl Default for CodeCoverage {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn visit_marks_only_rom_addresses() {
+        let mut coverage = CodeCoverage::new();
+        coverage.visit(0x0100);
+        coverage.visit(0x8000); // ROM外は無視
+        assert!(coverage.is_visited(0x0100));
+        assert!(!coverage.is_visited(0x0101));
+        assert!(!coverage.is_visited(0x8000));
+    }
+
+    #[test]
+    fn unvisited_ranges_across_byte_boundary() {
+        let mut coverage = CodeCoverage::new();
+        for addr in 0x0006..=0x0009 {
+            coverage.visit(addr); // ビット列の 1バイト目と 2バイト目にまたがる
+        }
+        coverage.visit(0x3FFF);
+        assert_eq!(
+            coverage.unvisited_ranges(),
+            vec![(0x0000, 0x0005), (0x000A, 0x3FFE), (0x4000, 0x7FFF)]
+        );
+        assert_eq!(coverage.coverage_percent(), 5.0 * 100.0 / 32768.0);
+    }
+
+    #[test]
+    fn full_coverage_and_clear() {
+        let mut coverage = CodeCoverage::new();
+        assert_eq!(coverage.unvisited_ranges(), vec![(0x0000, 0x7FFF)]);
+        for addr in 0..0x8000 {
+            coverage.visit(addr);
+        }
+        assert!(coverage.unvisited_ranges().is_empty());
+        assert_eq!(coverage.coverage_percent(), 100.0);
+
+        coverage.clear();
+        assert!(!coverage.is_visited(0x0000));
+        assert_eq!(coverage.coverage_percent(), 0.0);
+        assert_eq!(coverage.unvisited_ranges(), vec![(0x0000, 0x7FFF)]);
+    }
+}
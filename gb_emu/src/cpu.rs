@@ -1,7 +1,16 @@
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::fmt;
+use std::panic;
+use std::sync::Once;
+
+use crate::coverage::CodeCoverage;
+use crate::interrupt::InterruptFlags;
 use crate::mmu::MMU;
 
 // CPUのレジスタ構造
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Registers {
     pub a: u8, // アキュムレータ
     pub f: u8, // フラグレジスタ(0bZNHC0000)
@@ -30,9 +39,57 @@ impl Registers {
             pc: 0x0100, //ROMのエントリーポイント
         }
     }
+
+    // 16bitレジスタペア (上位, 下位)
+    pub fn get_af(&self) -> u16 {
+        ((self.a as u16) << 8) | (self.f as u16)
+    }
+
+    // get_af の別名 (以前からの呼び出し元向け)
+    pub fn af(&self) -> u16 {
+        self.get_af()
+    }
+
+    pub fn set_af(&mut self, v: u16) {
+        self.a = (v >> 8) as u8;
+        self.f = (v & 0xF0) as u8; // Fの下位4bitは常に0
+    }
+
+    pub fn get_bc(&self) -> u16 {
+        ((self.b as u16) << 8) | (self.c as u16)
+    }
+
+    pub fn set_bc(&mut self, v: u16) {
+        self.b = (v >> 8) as u8;
+        self.c = (v & 0xFF) as u8;
+    }
+
+    pub fn get_de(&self) -> u16 {
+        ((self.d as u16) << 8) | (self.e as u16)
+    }
+
+    pub fn set_de(&mut self, v: u16) {
+        self.d = (v >> 8) as u8;
+        self.e = (v & 0xFF) as u8;
+    }
+
+    pub fn get_hl(&self) -> u16 {
+        ((self.h as u16) << 8) | (self.l as u16)
+    }
+
+    pub fn set_hl(&mut self, v: u16) {
+        self.h = (v >> 8) as u8;
+        self.l = (v & 0xFF) as u8;
+    }
+}
+
+impl Default for Registers {
+    fn default() -> Self {
+        Self::new()
+    }
 }
 
-#[derive(PartialEq)]
+#[derive(Clone, Copy, PartialEq)]
 enum RegisterType {
     A,
     B,
@@ -41,10 +98,15 @@ enum RegisterType {
     E,
     H,
     L,
-    BC,
-    DE,
-    HL,
-    SP,
+    HL, // [HL]
+}
+
+// STOP命令で止まった理由
+#[derive(Debug, Clone, Copy, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum StopMode {
+    WaitJoypad,  // 低消費電力モード (ジョイパッド入力で復帰)
+    SpeedSwitch, // CGBの速度切り替え (切り替え後すぐ復帰)
 }
 
 #[derive(Debug, PartialEq)]
@@ -55,13 +117,75 @@ enum ConditionType {
     C,
 }
 
+// 割り込み (IE/IF のビット, ジャンプ先) を優先度の高い順に並べたもの
+pub const INTERRUPT_VECTORS: [(u8, u16); 5] = [
+    (0x01, 0x0040), // VBlank
+    (0x02, 0x0048), // LCD STAT
+    (0x04, 0x0050), // Timer
+    (0x08, 0x0058), // Serial
+    (0x10, 0x0060), // Joypad
+];
+
+// 命令実行前後のCPU状態 (トレース・クラッシュ報告用)
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct CpuSnapshot {
+    pub a: u8,
+    pub f: u8,
+    pub b: u8,
+    pub c: u8,
+    pub d: u8,
+    pub e: u8,
+    pub h: u8,
+    pub l: u8,
+    pub sp: u16,
+    pub pc: u16,
+    pub ime: bool,
+    pub halted: bool,
+    pub cycles: u64,
+}
+
+impl fmt::Display for CpuSnapshot {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "A: 0x{:02X}, F: 0x{:02X}, B: 0x{:02X}, C: 0x{:02X}, D: 0x{:02X}, E: 0x{:02X}, H: 0x{:02X}, L: 0x{:02X}, SP: 0x{:04X}, PC: 0x{:04X}, IME: {}, HALT: {}, cycles: {}",
+            self.a, self.f, self.b, self.c, self.d, self.e, self.h, self.l,
+            self.sp, self.pc, self.ime, self.halted, self.cycles
+        )
+    }
+}
+
+// パニック時に表示する直近のPC数
+const PC_HISTORY_LEN: usize = 16;
+
+// パニックフックが表示する状態 (install_panic_hook したスレッドでのみ更新)
+#[derive(Default)]
+struct CrashState {
+    snapshot: CpuSnapshot,     // 実行中の命令の開始時点
+    pc_history: VecDeque<u16>, // 古い順, 最後が実行中の命令
+}
+
+thread_local! {
+    static CRASH_STATE: RefCell<Option<CrashState>> = const { RefCell::new(None) };
+}
+
+static PANIC_HOOK: Once = Once::new();
+
 // LR35902 CPU 定義
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct CPU {
     pub regs: Registers, // レジスタ
     pub mmu: MMU,        //メモリ管理ユニット
-    pub stopped: bool,
+    pub stopped_mode: Option<StopMode>, // STOP中のみ Some
     pub halted: bool,
     pub ime: bool,
+    pub ime_pending: bool,      // EI の次の命令の後で IME を有効にする
+    pub coverage_enabled: bool, // trueの間、fetchしたROMアドレスを記録
+    pub coverage: CodeCoverage, // 実行済みROMアドレス
+    cycles: u64,                // 起動からの累計Tサイクル数
+    #[cfg_attr(feature = "serde", serde(skip))]
+    crash_report: bool,         // trueの間、パニック時に表示する状態を記録
 }
 
 impl CPU {
@@ -69,54 +193,143 @@ impl CPU {
         Self {
             regs: Registers::new(),
             mmu,
-            stopped: false,
+            stopped_mode: None,
             halted: false,
             ime: true,
+            ime_pending: false,
+            coverage_enabled: false,
+            coverage: CodeCoverage::new(),
+            cycles: 0,
+            crash_report: false,
         }
     }
 
+    pub const fn interrupt_vectors() -> [(u8, u16); 5] {
+        INTERRUPT_VECTORS
+    }
+
+    // 起動からの累計Tサイクル数 (M-cycle = Tサイクル / 4)
+    pub fn cycles(&self) -> u64 {
+        self.cycles
+    }
+
     // CPUを1クロック実行
     pub fn step(&mut self) {
+        self.mmu.set_cycle(self.cycles); // アクセス履歴に命令開始時のサイクル数を付ける
+        let dispatch_cycles = self.handle_interrupts();
+        if dispatch_cycles > 0 {
+            self.advance(dispatch_cycles);
+            return;
+        }
+        // HALT/STOP 中は命令を実行せずに時間だけ進める
+        if self.halted || self.stopped_mode.is_some() {
+            self.advance(4);
+            return;
+        }
+        if self.crash_report {
+            self.record_crash_state();
+        }
+        let enable_ime = self.ime_pending; // この命令の前に EI が実行されていたか
         let opcode = self.fetch();
-        self.execute(opcode);
+        let cycles = self.execute(opcode);
+        // 直後の DI で ime_pending が消えていれば有効にしない
+        if enable_ime && self.ime_pending {
+            self.ime = true;
+            self.ime_pending = false;
+        }
+        self.advance(cycles);
+    }
+
+    // 経過したTサイクルを加算し、OAM DMA を進める
+    fn advance(&mut self, cycles: u8) {
+        self.cycles += cycles as u64;
+        self.mmu.step_dma(cycles / 4);
+    }
+
+    pub fn snapshot(&self) -> CpuSnapshot {
+        CpuSnapshot {
+            a: self.regs.a,
+            f: self.regs.f,
+            b: self.regs.b,
+            c: self.regs.c,
+            d: self.regs.d,
+            e: self.regs.e,
+            h: self.regs.h,
+            l: self.regs.l,
+            sp: self.regs.sp,
+            pc: self.regs.pc,
+            ime: self.ime,
+            halted: self.halted,
+            cycles: self.cycles,
+        }
+    }
+
+    // パニック時に実行中の命令のCPU状態と直近のPCを表示する
+    // フックの登録はプロセスで1回だけ, 表示後は元のフックに任せる
+    pub fn install_panic_hook(&mut self) {
+        PANIC_HOOK.call_once(|| {
+            let previous = panic::take_hook();
+            panic::set_hook(Box::new(move |info| {
+                CRASH_STATE.with(|state| {
+                    // 記録中にパニックした場合は借用できないので何も表示しない
+                    if let Ok(state) = state.try_borrow()
+                        && let Some(state) = state.as_ref()
+                    {
+                        eprintln!("CPU state: {}", state.snapshot);
+                        let history: Vec<String> =
+                            state.pc_history.iter().map(|pc| format!("0x{:04X}", pc)).collect();
+                        eprintln!("Recent PCs: {}", history.join(" "));
+                    }
+                });
+                previous(info);
+            }));
+        });
+        self.crash_report = true;
+    }
+
+    // 命令を実行する前の状態をパニックフック用に保存する
+    fn record_crash_state(&self) {
+        let snapshot = self.snapshot();
+        CRASH_STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            let state = state.get_or_insert_with(CrashState::default);
+            if state.pc_history.len() == PC_HISTORY_LEN {
+                state.pc_history.pop_front();
+            }
+            state.pc_history.push_back(snapshot.pc);
+            state.snapshot = snapshot;
+        });
+    }
+
+
+    // 1命令実行した後の状態をコールバックに渡す (デバッガ用)
+    pub fn single_step_with_callback<F: FnMut(&CPU)>(&mut self, mut cb: F) {
+        self.step();
+        cb(self);
     }
 
     // 命令フェッチ
+    // オペランド (即値) もここで読むので、カバレッジには命令の全バイトが記録される
     fn fetch(&mut self) -> u8 {
+        if self.coverage_enabled {
+            self.coverage.visit(self.regs.pc);
+        }
         let opcode = self.mmu.read_byte(self.regs.pc);
         self.regs.pc = self.regs.pc.wrapping_add(1);
         opcode
     }
 
-    fn ld_r16mem(&mut self, r16_high: u8, r16_low: u8) {
-        let addr = ((r16_high as u16) << 8) | (r16_low as u16);
+    fn ld_r16mem(&mut self, addr: u16) {
         self.mmu.write_byte(addr, self.regs.a);
     }
 
-    fn ld_a_r16mem(&mut self, r16_high: u8, r16_low: u8) {
-        let addr = ((r16_high as u16) << 8) | (r16_low as u16);
+    fn ld_a_r16mem(&mut self, addr: u16) {
         let value = self.mmu.read_byte(addr);
         self.regs.a = value;
     }
 
-    fn get_inc_r16_value(&mut self, r16_high: u8, r16_low: u8) -> u16 {
-        let value = ((r16_high as u16) << 8) | (r16_low as u16);
-        value.wrapping_add(1)
-    }
-
-    fn get_dec_r16_value(&mut self, r16_high: u8, r16_low: u8) -> u16 {
-        let value = ((r16_high as u16) << 8) | (r16_low as u16);
-        value.wrapping_sub(1)
-    }
-
-    fn add_hl_r16(&mut self, r16_high: u8, r16_low: u8, register_type: RegisterType) {
-        let value: u16;
-        if register_type == RegisterType::SP {
-            value = self.regs.sp;
-        } else {
-            value = ((r16_high as u16) << 8) | (r16_low as u16);
-        }
-        let hl = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+    fn add_hl_r16(&mut self, value: u16) {
+        let hl = self.regs.get_hl();
         let result = hl.wrapping_add(value);
 
         self.regs.f &= 0x80; // Z以外クリア
@@ -127,27 +340,11 @@ impl CPU {
             self.regs.f |= 0x10; // C
         }
 
-        self.regs.h = (result >> 8) as u8;
-        self.regs.l = (result & 0xFF) as u8;
+        self.regs.set_hl(result);
     }
 
     fn inc_r8(&mut self, register_type: RegisterType) {
-        let mut value: u8 = 0;
-        let mut addr: u16 = 0;
-        match register_type {
-            RegisterType::A => value = self.regs.a,
-            RegisterType::B => value = self.regs.b,
-            RegisterType::C => value = self.regs.c,
-            RegisterType::D => value = self.regs.d,
-            RegisterType::E => value = self.regs.e,
-            RegisterType::H => value = self.regs.h,
-            RegisterType::L => value = self.regs.l,
-            RegisterType::HL => {
-                addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
-                value = self.mmu.read_byte(addr);
-            }
-            _ => {}
-        }
+        let value = self.read_r8(register_type);
         let result = value.wrapping_add(1);
 
         self.regs.f &= 0x10; // C以外クリア
@@ -158,36 +355,11 @@ impl CPU {
             self.regs.f |= 0x20; // H
         }
 
-        match register_type {
-            RegisterType::A => self.regs.a = result,
-            RegisterType::B => self.regs.b = result,
-            RegisterType::C => self.regs.c = result,
-            RegisterType::D => self.regs.d = result,
-            RegisterType::E => self.regs.e = result,
-            RegisterType::H => self.regs.h = result,
-            RegisterType::L => self.regs.l = result,
-            RegisterType::HL => self.mmu.write_byte(addr, result),
-            _ => {}
-        }
+        self.write_r8(register_type, result);
     }
 
     fn dec_r8(&mut self, register_type: RegisterType) {
-        let mut value: u8 = 0;
-        let mut addr: u16 = 0;
-        match register_type {
-            RegisterType::A => value = self.regs.a,
-            RegisterType::B => value = self.regs.b,
-            RegisterType::C => value = self.regs.c,
-            RegisterType::D => value = self.regs.d,
-            RegisterType::E => value = self.regs.e,
-            RegisterType::H => value = self.regs.h,
-            RegisterType::L => value = self.regs.l,
-            RegisterType::HL => {
-                addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
-                value = self.mmu.read_byte(addr);
-            }
-            _ => {}
-        }
+        let value = self.read_r8(register_type);
         let result = value.wrapping_sub(1);
 
         self.regs.f &= 0x10; // C以外クリア
@@ -195,25 +367,30 @@ impl CPU {
             self.regs.f |= 0x80; // Z
         }
         self.regs.f |= 0x40; // N
-        if (value & 0x10) == 0x10 {
-            self.regs.f |= 0x20; // H
+        if (value & 0x0F) == 0x00 {
+            self.regs.f |= 0x20; // H (bit4からの借り)
         }
 
-        match register_type {
-            RegisterType::A => self.regs.a = result,
-            RegisterType::B => self.regs.b = result,
-            RegisterType::C => self.regs.c = result,
-            RegisterType::D => self.regs.d = result,
-            RegisterType::E => self.regs.e = result,
-            RegisterType::H => self.regs.h = result,
-            RegisterType::L => self.regs.l = result,
-            RegisterType::HL => self.mmu.write_byte(addr, result),
-            _ => {}
-        }
+        self.write_r8(register_type, result);
     }
 
-    fn add_a(&mut self, r8_value: u8) {
-        self.regs.a = self.regs.a.wrapping_add(r8_value);
+    // ADD A, r8 / ADD A, n 共通 (Z, H, C を更新, N はクリア)
+    fn add_a_impl(&mut self, value: u8) {
+        let a = self.regs.a;
+        let result = a.wrapping_add(value);
+
+        self.regs.f = 0x00;
+        if result == 0 {
+            self.regs.f |= 0x80; // Z
+        }
+        if (a & 0x0F) + (value & 0x0F) > 0x0F {
+            self.regs.f |= 0x20; // H
+        }
+        if (a as u16) + (value as u16) > 0xFF {
+            self.regs.f |= 0x10; // C
+        }
+
+        self.regs.a = result;
     }
 
     fn adc_a(&mut self, r8_value: u8) {
@@ -236,34 +413,105 @@ impl CPU {
         self.regs.a = result;
     }
 
-    fn sub_a(&mut self, register_type: RegisterType) {
+    fn sbc_a(&mut self, r8_value: u8) {
         let a = self.regs.a;
-        let r8_value: u8;
+        let carry = if self.regs.f & 0x10 != 0 { 1 } else { 0 };
+
+        let result = a.wrapping_sub(r8_value).wrapping_sub(carry);
+
+        self.regs.f = 0x40; // N
+        if result == 0 {
+            self.regs.f |= 0x80; // Z
+        }
+        if (a & 0x0F) < (r8_value & 0x0F) + carry {
+            self.regs.f |= 0x20; // H
+        }
+        if (a as u16) < (r8_value as u16) + (carry as u16) {
+            self.regs.f |= 0x10; // C
+        }
+
+        self.regs.a = result;
+    }
+
+    // AND は H が常に1
+    fn and_a(&mut self, r8_value: u8) {
+        self.regs.a &= r8_value;
+        self.regs.f = 0x20 | if self.regs.a == 0 { 0x80 } else { 0 };
+    }
+
+    // XOR A, A で A を0にするのが定番
+    fn xor_a(&mut self, r8_value: u8) {
+        self.regs.a ^= r8_value;
+        self.regs.f = if self.regs.a == 0 { 0x80 } else { 0 };
+    }
+
+    fn or_a(&mut self, r8_value: u8) {
+        self.regs.a |= r8_value;
+        self.regs.f = if self.regs.a == 0 { 0x80 } else { 0 };
+    }
+
+    fn read_r8(&mut self, register_type: RegisterType) -> u8 {
         match register_type {
-            RegisterType::A => r8_value = self.regs.a,
-            RegisterType::B => r8_value = self.regs.b,
-            RegisterType::C => r8_value = self.regs.c,
-            RegisterType::D => r8_value = self.regs.d,
-            RegisterType::E => r8_value = self.regs.e,
-            RegisterType::H => r8_value = self.regs.h,
-            RegisterType::L => r8_value = self.regs.l,
+            RegisterType::A => self.regs.a,
+            RegisterType::B => self.regs.b,
+            RegisterType::C => self.regs.c,
+            RegisterType::D => self.regs.d,
+            RegisterType::E => self.regs.e,
+            RegisterType::H => self.regs.h,
+            RegisterType::L => self.regs.l,
             RegisterType::HL => {
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
-                r8_value = self.mmu.read_byte(addr);
-            },
-            _ => r8_value = 0,
+                let addr = self.regs.get_hl();
+                self.mmu.read_byte(addr)
+            }
         }
+    }
 
-        let result = a.wrapping_add(r8_value);
+    fn write_r8(&mut self, register_type: RegisterType, value: u8) {
+        match register_type {
+            RegisterType::A => self.regs.a = value,
+            RegisterType::B => self.regs.b = value,
+            RegisterType::C => self.regs.c = value,
+            RegisterType::D => self.regs.d = value,
+            RegisterType::E => self.regs.e = value,
+            RegisterType::H => self.regs.h = value,
+            RegisterType::L => self.regs.l = value,
+            RegisterType::HL => {
+                let addr = self.regs.get_hl();
+                self.mmu.write_byte(addr, value);
+            }
+        }
+    }
 
-        // TODO FIX
-        self.regs.f = 0x00;
+    // SUB / CP 共通の減算
+    // フラグは同じ計算で、write_result が true (SUB) のときだけ A に書き戻す
+    fn alu_sub(&mut self, src: u8, write_result: bool) {
+        let a = self.regs.a;
+        let result = a.wrapping_sub(src);
+
+        self.regs.f = 0x40; // N
         if result == 0 {
             self.regs.f |= 0x80; // Z
         }
-        self.regs.f |= 0x40;     // N
+        if (a & 0x0F) < (src & 0x0F) {
+            self.regs.f |= 0x20; // H
+        }
+        if a < src {
+            self.regs.f |= 0x10; // C
+        }
 
-        self.regs.a = result;
+        if write_result {
+            self.regs.a = result;
+        }
+    }
+
+    fn sub_a(&mut self, register_type: RegisterType) {
+        let value = self.read_r8(register_type);
+        self.alu_sub(value, true);
+    }
+
+    fn cp_a(&mut self, register_type: RegisterType) {
+        let value = self.read_r8(register_type);
+        self.alu_sub(value, false);
     }
 
     fn rlca(&mut self) {
@@ -319,32 +567,37 @@ impl CPU {
     }
 
     fn daa(&mut self) {
-        let mut correction = 0;
-        let mut carry = false;
-
-        if (self.regs.f & 0x20 != 0) || (self.regs.a & 0x0F) > 9 {
-            correction |= 0x06;
-        }
-
-        if (self.regs.f & 0x10 != 0) || (self.regs.a > 0x99) {
-            correction |= 0x60;
-            carry = true;
-        }
+        let mut a = self.regs.a;
+        let mut carry = self.regs.f & 0x10 != 0;
+        let half_carry = self.regs.f & 0x20 != 0;
 
         if (self.regs.f & 0x40) == 0 {
-            self.regs.a = self.regs.a.wrapping_add(correction);
+            // 加算後: 桁あふれした桁を補正
+            if carry || a > 0x99 {
+                a = a.wrapping_add(0x60);
+                carry = true;
+            }
+            if half_carry || (a & 0x0F) > 0x09 {
+                a = a.wrapping_add(0x06);
+            }
         } else {
-            self.regs.a = self.regs.a.wrapping_sub(correction);
+            // 減算後: H, C フラグのみで補正 (値の範囲は見ない)
+            if carry {
+                a = a.wrapping_sub(0x60);
+            }
+            if half_carry {
+                a = a.wrapping_sub(0x06);
+            }
         }
 
-        self.regs.f &= 0x10 | 0x40; // Keep C, N
-        if self.regs.a == 0 {
+        self.regs.a = a;
+        self.regs.f &= 0x40; // N のみ保持
+        if a == 0 {
             self.regs.f |= 0x80; // Z
         }
         if carry {
             self.regs.f |= 0x10; // C
         }
-        self.regs.f &= !0x20; // Clear H
     }
 
     fn cpl(&mut self) {
@@ -368,52 +621,187 @@ impl CPU {
         self.regs.f &= !0x60; // Reset N, H
     }
 
+    // SP + e8 を計算してフラグを更新する (ADD SP, e8 / LD HL, SP+e8 共通)
+    // H, C は下位バイト同士の符号なし加算で判定するため、
+    // e8 が負でも SP=0x0000, e8=0xFF では H=0, C=0 になる
+    fn add_sp_e8(&mut self) -> u16 {
+        let e8 = self.fetch();
+        let sp = self.regs.sp;
+        let result = sp.wrapping_add(e8 as i8 as i16 as u16);
+
+        self.regs.f = 0x00; // Z, N はクリア
+        if (sp & 0x000F) + (e8 as u16 & 0x000F) > 0x000F {
+            self.regs.f |= 0x20; // H
+        }
+        if (sp & 0x00FF) + (e8 as u16) > 0x00FF {
+            self.regs.f |= 0x10; // C
+        }
+
+        result
+    }
+
     fn jr_e8(&mut self) {
         let offset = self.fetch() as i8;
         self.regs.pc = self.regs.pc.wrapping_add(offset as i16 as u16);
     }
 
-    fn jr_cond_e8(&mut self, condition: ConditionType) {
-        let offset = self.fetch() as i8;
-        let mut add_flg = false;
+    // フラグが条件を満たしているか
+    fn check_condition(&self, condition: ConditionType) -> bool {
         match condition {
-            ConditionType::NZ => {
-                if self.regs.f & 0x80 == 0 {
-                    add_flg = true;
-                }
-            }
-            ConditionType::Z => {
-                if self.regs.f & 0x80 != 0 {
-                    add_flg = true;
-                }
-            }
-            ConditionType::NC => {
-                if self.regs.f & 0x10 == 0 {
-                    add_flg = true;
-                }
-            }
-            ConditionType::C => {
-                if self.regs.f & 0x10 != 0 {
-                    add_flg = true;
-                }
-            }
+            ConditionType::NZ => self.regs.f & 0x80 == 0,
+            ConditionType::Z => self.regs.f & 0x80 != 0,
+            ConditionType::NC => self.regs.f & 0x10 == 0,
+            ConditionType::C => self.regs.f & 0x10 != 0,
         }
+    }
 
-        if add_flg == true {
+    // 分岐したら12, しなければ8サイクル
+    fn jr_cond_e8(&mut self, condition: ConditionType) -> u8 {
+        let offset = self.fetch() as i8;
+        if self.check_condition(condition) {
             self.regs.pc = self.regs.pc.wrapping_add(offset as u16);
+            12
+        } else {
+            8
+        }
+    }
+
+    // スタックに16bit値を積む (SP=0x0000 でも 0xFFFF, 0xFFFE に折り返す)
+    // 実機は上位バイト (SP-1) を先に書くため write_word (下位が先) は使わない
+    fn push_u16(&mut self, value: u16) {
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        self.mmu.write_byte(self.regs.sp, (value >> 8) as u8); // 上位バイト
+        self.regs.sp = self.regs.sp.wrapping_sub(1);
+        self.mmu.write_byte(self.regs.sp, (value & 0xFF) as u8); // 下位バイト
+    }
+
+    // レジスタペアを積む (high が SP-1, low が SP-2)
+    fn push_r16(&mut self, high: u8, low: u8) {
+        self.push_u16(((high as u16) << 8) | (low as u16));
+    }
+
+    // レジスタペアを取り出す (high, low)
+    fn pop_r16(&mut self) -> (u8, u8) {
+        let value = self.pop_u16();
+        ((value >> 8) as u8, (value & 0xFF) as u8)
+    }
+
+    // スタックから16bit値を取り出す (下位バイトが先なので read_word と同じ順序)
+    fn pop_u16(&mut self) -> u16 {
+        let value = self.mmu.read_word(self.regs.sp);
+        self.regs.sp = self.regs.sp.wrapping_add(2);
+        value
+    }
+
+    // 16bit即値を読む (下位バイトが先)
+    fn fetch_u16(&mut self) -> u16 {
+        let low = self.fetch();
+        let high = self.fetch();
+        ((high as u16) << 8) | (low as u16)
+    }
+
+    // 分岐したら16, しなければ12サイクル (addr は条件に関係なく読み終えている)
+    fn jp_cond(&mut self, condition: ConditionType, addr: u16) -> u8 {
+        if self.check_condition(condition) {
+            self.regs.pc = addr;
+            16
+        } else {
+            12
+        }
+    }
+
+    // CALL nn: 戻り先 (次の命令) を積んでジャンプ (24サイクル)
+    fn call_nn(&mut self) -> u8 {
+        let addr = self.fetch_u16();
+        self.push_u16(self.regs.pc);
+        self.regs.pc = addr;
+        24
+    }
+
+    // CALL cc, nn: 呼んだら24, 呼ばなければ12サイクル (オペランドは常に読む)
+    fn call_cond(&mut self, condition: ConditionType) -> u8 {
+        let addr = self.fetch_u16();
+        if self.check_condition(condition) {
+            self.push_u16(self.regs.pc);
+            self.regs.pc = addr;
+            24
+        } else {
+            12
+        }
+    }
+
+    // RST: 固定アドレス (0x00, 0x08, ..., 0x38) への1バイトCALL, 16サイクル
+    fn rst(&mut self, vector: u8) -> u8 {
+        self.push_u16(self.regs.pc);
+        self.regs.pc = vector as u16;
+        16
+    }
+
+    // RET: 16サイクル
+    // フェッチ4 + 戻り先の下位バイト4 + 上位バイト4 + PCを設定する内部遅延4
+    fn ret(&mut self) -> u8 {
+        let (high, low) = self.pop_r16();
+        self.regs.pc = ((high as u16) << 8) | (low as u16);
+        16
+    }
+
+    // RETI: RET と同じ16サイクル (IME は EI と違い即座に有効)
+    fn reti(&mut self) -> u8 {
+        self.ime = true;
+        self.ret()
+    }
+
+    // RET cc: 条件判定の4サイクルが加わり、戻ったら20, 戻らなければ8サイクル
+    fn ret_cond(&mut self, condition: ConditionType) -> u8 {
+        if self.check_condition(condition) {
+            self.ret() + 4
+        } else {
+            8
         }
     }
 
     fn stop(&mut self) {
+        // STOP は 0x10 0x00 の2バイト命令 (2バイト目は読み捨て)
+        self.fetch();
         println!("CPU STOP");
-        self.stopped = true;
+        // CGBで KEY1 bit0 が立っていれば速度切り替え
+        if self.mmu.speed_switch_armed() {
+            self.mmu.switch_speed();
+            self.stopped_mode = Some(StopMode::SpeedSwitch);
+        } else {
+            self.stopped_mode = Some(StopMode::WaitJoypad);
+        }
     }
 
-    fn handle_interrupts(&mut self) {
-        if self.stopped {
+    // 割り込みの受け付け (ディスパッチした場合は消費サイクル数を返す)
+    fn handle_interrupts(&mut self) -> u8 {
+        let resume = match self.stopped_mode {
+            Some(StopMode::SpeedSwitch) => true,
+            Some(StopMode::WaitJoypad) => self.mmu.interrupt_flag().joypad(),
+            None => false,
+        };
+        if resume {
             println!("CPU START");
-            self.stopped = false;
+            self.stopped_mode = None;
+        }
+        if self.stopped_mode.is_some() {
+            return 0;
+        }
+
+        let pending = self.mmu.interrupt_enable().0 & self.mmu.interrupt_flag().0;
+        let Some(&(bit, vector)) = INTERRUPT_VECTORS.iter().find(|&&(bit, _)| pending & bit != 0) else {
+            return 0;
+        };
+        // IME に関係なく HALT は解除される
+        self.halted = false;
+        if !self.ime {
+            return 0;
         }
+        self.ime = false;
+        self.mmu.clear_interrupt(InterruptFlags(bit));
+        self.push_u16(self.regs.pc);
+        self.regs.pc = vector;
+        20
     }
 
     fn ld_r8_r8(&mut self, dst_reg: RegisterType, src_reg: RegisterType) {
@@ -426,10 +814,9 @@ impl CPU {
             RegisterType::H => self.regs.h,
             RegisterType::L => self.regs.l,
             RegisterType::HL => {
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
                 self.mmu.read_byte(addr)
             }
-            _ => 0,
         };
         match dst_reg {
             RegisterType::A => self.regs.a = value,
@@ -438,7 +825,7 @@ impl CPU {
             RegisterType::D => self.regs.d = value,
             RegisterType::E => self.regs.e = value,
             RegisterType::HL => {
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
                 self.mmu.write_byte(addr, value);
             }
             _ => {}
@@ -446,283 +833,532 @@ impl CPU {
     }
 
     fn halt(&mut self) {
-        if self.ime == true {
+        let interrupted_enable = self.mmu.read_byte(0xFFFF);
+        let interrupted_flag = self.mmu.read_byte(0xFF0F);
+        // IME=0 で割り込みが保留中なら HALT せずに次の命令へ進む (HALTバグは未対応)
+        if self.ime || interrupted_enable & interrupted_flag == 0 {
             self.halted = true;
-        } else {
-            let interrupted_enable = self.mmu.read_byte(0xFFFF);
-            let interrupted_flag = self.mmu.read_byte(0xFF0F);
-            if interrupted_enable & interrupted_flag != 0 {
-                self.halted = false;
-            }
         }
     }
 
-    fn execute(&mut self, opcode: u8) {
+    // CB命令のシフト・ローテート共通のフラグ更新 (Z, C のみ立つ)
+    fn set_shift_flags(&mut self, result: u8, carry: bool) {
+        self.regs.f = 0x00;
+        if result == 0 {
+            self.regs.f |= 0x80; // Z
+        }
+        if carry {
+            self.regs.f |= 0x10; // C
+        }
+    }
+
+    fn cb_rlc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(1);
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    fn cb_rrc(&mut self, value: u8) -> u8 {
+        let result = value.rotate_right(1);
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn cb_rl(&mut self, value: u8) -> u8 {
+        let carry = (self.regs.f & 0x10) >> 4;
+        let result = (value << 1) | carry;
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    fn cb_rr(&mut self, value: u8) -> u8 {
+        let carry = (self.regs.f & 0x10) >> 4;
+        let result = (value >> 1) | (carry << 7);
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn cb_sla(&mut self, value: u8) -> u8 {
+        let result = value << 1;
+        self.set_shift_flags(result, value & 0x80 != 0);
+        result
+    }
+
+    fn cb_sra(&mut self, value: u8) -> u8 {
+        let result = (value >> 1) | (value & 0x80); // bit7 は保持
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    fn cb_swap(&mut self, value: u8) -> u8 {
+        let result = value.rotate_left(4);
+        self.set_shift_flags(result, false);
+        result
+    }
+
+    fn cb_srl(&mut self, value: u8) -> u8 {
+        let result = value >> 1;
+        self.set_shift_flags(result, value & 0x01 != 0);
+        result
+    }
+
+    // BIT はフラグだけを更新する (C は保持)
+    fn cb_bit(bit: u8, value: u8, flags: &mut u8) {
+        *flags &= 0x10; // C以外クリア
+        *flags |= 0x20; // H
+        if value & (1 << bit) == 0 {
+            *flags |= 0x80; // Z
+        }
+    }
+
+    // RES/SET はフラグを変更しない
+    fn cb_res(bit: u8, value: u8) -> u8 {
+        value & !(1 << bit)
+    }
+
+    fn cb_set(bit: u8, value: u8) -> u8 {
+        value | (1 << bit)
+    }
+
+    // CBプレフィックス命令を実行し、T-cycle数を返す
+    // opcode = |操作(2bit)|bit番号 or シフト種別(3bit)|レジスタ(3bit)|
+    fn execute_cb(&mut self, cb_opcode: u8) -> u8 {
+        let register_type = match cb_opcode & 0x07 {
+            0 => RegisterType::B,
+            1 => RegisterType::C,
+            2 => RegisterType::D,
+            3 => RegisterType::E,
+            4 => RegisterType::H,
+            5 => RegisterType::L,
+            6 => RegisterType::HL,
+            _ => RegisterType::A,
+        };
+        let bit = (cb_opcode >> 3) & 0x07;
+        let is_hl = register_type == RegisterType::HL;
+        let value = self.read_r8(register_type);
+
+        let result = match cb_opcode >> 6 {
+            0 => match bit {
+                0 => self.cb_rlc(value),  // RLC r8
+                1 => self.cb_rrc(value),  // RRC r8
+                2 => self.cb_rl(value),   // RL r8
+                3 => self.cb_rr(value),   // RR r8
+                4 => self.cb_sla(value),  // SLA r8
+                5 => self.cb_sra(value),  // SRA r8
+                6 => self.cb_swap(value), // SWAP r8
+                _ => self.cb_srl(value),  // SRL r8
+            },
+            1 => {
+                // BIT b, r8 (書き戻しなし)
+                Self::cb_bit(bit, value, &mut self.regs.f);
+                return if is_hl { 12 } else { 8 };
+            }
+            2 => Self::cb_res(bit, value), // RES b, r8
+            _ => Self::cb_set(bit, value), // SET b, r8
+        };
+        self.write_r8(register_type, result);
+
+        if is_hl { 16 } else { 8 }
+    }
+
+    // 命令を実行し, かかったTサイクル数を返す
+    fn execute(&mut self, opcode: u8) -> u8 {
         match opcode {
-            0x00 => { /* Nothing */ }
-            0x02 => self.ld_r16mem(self.regs.b, self.regs.c), // LD [BC], A
-            0x12 => self.ld_r16mem(self.regs.d, self.regs.e), // LD [DE], A
+            0x00 => 4, // NOP
+            0x02 => { self.ld_r16mem(self.regs.get_bc()); 8 } // LD [BC], A
+            0x12 => { self.ld_r16mem(self.regs.get_de()); 8 } // LD [DE], A
             0x22 => {
                 // LD [HL+], A
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
                 self.mmu.write_byte(addr, self.regs.a);
                 let hl = addr.wrapping_add(1);
-                self.regs.h = (hl >> 8) as u8;
-                self.regs.l = (hl & 0xFF) as u8;
+                self.regs.set_hl(hl);
+                8
             }
             0x32 => {
                 // LD [HL-], A
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
                 self.mmu.write_byte(addr, self.regs.a);
                 let hl = addr.wrapping_sub(1);
-                self.regs.h = (hl >> 8) as u8;
-                self.regs.l = (hl & 0xFF) as u8;
+                self.regs.set_hl(hl);
+                8
             }
-            0x0A => self.ld_a_r16mem(self.regs.b, self.regs.c), // LD A, [BC]
-            0x1A => self.ld_a_r16mem(self.regs.d, self.regs.e), // LD A, [DE]
+            0x0A => { self.ld_a_r16mem(self.regs.get_bc()); 8 } // LD A, [BC]
+            0x1A => { self.ld_a_r16mem(self.regs.get_de()); 8 } // LD A, [DE]
             0x2A => {
                 // LD A, [HL+]
-                self.ld_a_r16mem(self.regs.h, self.regs.l);
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
+                self.ld_a_r16mem(addr);
                 let hl = addr.wrapping_add(1);
-                self.regs.h = (hl >> 8) as u8;
-                self.regs.l = (hl & 0xFF) as u8;
+                self.regs.set_hl(hl);
+                8
             }
             0x3A => {
-                // LD A, [HL-]
-                self.ld_a_r16mem(self.regs.h, self.regs.l);
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                // LD A, [HL-] (読んだアドレスから1引く)
+                let addr = self.regs.get_hl();
+                self.regs.a = self.mmu.read_byte(addr);
                 let hl = addr.wrapping_sub(1);
-                self.regs.h = (hl >> 8) as u8;
-                self.regs.l = (hl & 0xFF) as u8;
+                self.regs.set_hl(hl);
+                8
             }
             0x08 => {
                 // LD [imm16], SP
                 let low = self.fetch();
                 let high = self.fetch();
                 let addr = ((high as u16) << 8) | (low as u16);
-
-                let sp_low = (self.regs.sp & 0xFF) as u8;
-                let sp_high = (self.regs.sp >> 8) as u8;
-
-                self.mmu.write_byte(addr, sp_low);
-                self.mmu.write_byte(addr + 1, sp_high);
+                self.mmu.write_word(addr, self.regs.sp);
+                20
             }
             0x03 => {
                 // INC BC
-                let value = self.get_inc_r16_value(self.regs.b, self.regs.c);
-                self.regs.b = (value >> 8) as u8;
-                self.regs.c = (value & 0xFF) as u8;
+                self.regs.set_bc(self.regs.get_bc().wrapping_add(1));
+                8
             }
             0x13 => {
                 // INC DE
-                let value = self.get_inc_r16_value(self.regs.d, self.regs.e);
-                self.regs.d = (value >> 8) as u8;
-                self.regs.e = (value & 0xFF) as u8;
+                self.regs.set_de(self.regs.get_de().wrapping_add(1));
+                8
             }
             0x23 => {
                 // INC HL
-                let value = self.get_inc_r16_value(self.regs.h, self.regs.l);
-                self.regs.h = (value >> 8) as u8;
-                self.regs.l = (value & 0xFF) as u8;
+                self.regs.set_hl(self.regs.get_hl().wrapping_add(1));
+                8
             }
             0x33 => {
                 // INC SP
                 self.regs.sp = self.regs.sp.wrapping_add(1);
+                8
             }
             0x0B => {
                 // DEC BC
-                let value = self.get_dec_r16_value(self.regs.b, self.regs.c);
-                self.regs.b = (value >> 8) as u8;
-                self.regs.c = (value & 0xFF) as u8;
+                self.regs.set_bc(self.regs.get_bc().wrapping_sub(1));
+                8
             }
             0x1B => {
                 // DEC DE
-                let value = self.get_dec_r16_value(self.regs.d, self.regs.e);
-                self.regs.d = (value >> 8) as u8;
-                self.regs.e = (value & 0xFF) as u8;
+                self.regs.set_de(self.regs.get_de().wrapping_sub(1));
+                8
             }
             0x2B => {
                 // DEC HL
-                let value = self.get_dec_r16_value(self.regs.h, self.regs.l);
-                self.regs.h = (value >> 8) as u8;
-                self.regs.l = (value & 0xFF) as u8;
-            }
-            0x3B => self.regs.sp = self.regs.sp.wrapping_sub(1), // DEC SP
-            0x09 => self.add_hl_r16(self.regs.b, self.regs.c, RegisterType::BC), // ADD HL, BC
-            0x19 => self.add_hl_r16(self.regs.d, self.regs.e, RegisterType::DE), // ADD HL, DE
-            0x29 => self.add_hl_r16(self.regs.h, self.regs.l, RegisterType::HL), // ADD HL, HL
-            0x39 => self.add_hl_r16(0, 0, RegisterType::SP),     // ADD HL, SP
-            0x3C => self.inc_r8(RegisterType::A),                // INC A
-            0x04 => self.inc_r8(RegisterType::B),                // INC B
-            0x0C => self.inc_r8(RegisterType::C),                // INC C
-            0x14 => self.inc_r8(RegisterType::D),                // INC D
-            0x1C => self.inc_r8(RegisterType::E),                // INC E
-            0x24 => self.inc_r8(RegisterType::H),                // INC H
-            0x2C => self.inc_r8(RegisterType::L),                // INC L
-            0x34 => self.inc_r8(RegisterType::HL),               // INC [HL]
-            0x3D => self.dec_r8(RegisterType::A),                // DEC A
-            0x05 => self.dec_r8(RegisterType::B),                // DEC B
-            0x0D => self.dec_r8(RegisterType::C),                // DEC C
-            0x15 => self.dec_r8(RegisterType::D),                // DEC D
-            0x1D => self.dec_r8(RegisterType::E),                // DEC E
-            0x25 => self.dec_r8(RegisterType::H),                // DEC H
-            0x2D => self.dec_r8(RegisterType::L),                // DEC L
-            0x35 => self.dec_r8(RegisterType::HL),               // DEC [HL]
-            0x80 => self.add_a(self.regs.b),                     // ADD A, B
-            0x81 => self.add_a(self.regs.c),                     // ADD A, C
-            0x82 => self.add_a(self.regs.d),                     // ADD A, D
-            0x83 => self.add_a(self.regs.e),                     // ADD A, E
-            0x84 => self.add_a(self.regs.h),                     // ADD A, H
-            0x85 => self.add_a(self.regs.l),                     // ADD A, L
+                self.regs.set_hl(self.regs.get_hl().wrapping_sub(1));
+                8
+            }
+            0x3B => { self.regs.sp = self.regs.sp.wrapping_sub(1); 8 }                 // DEC SP
+            0x09 => { self.add_hl_r16(self.regs.get_bc()); 8 }                         // ADD HL, BC
+            0x19 => { self.add_hl_r16(self.regs.get_de()); 8 }                         // ADD HL, DE
+            0x29 => { self.add_hl_r16(self.regs.get_hl()); 8 }                         // ADD HL, HL
+            0x39 => { self.add_hl_r16(self.regs.sp); 8 }                               // ADD HL, SP
+            0x3C => { self.inc_r8(RegisterType::A); 4 }                                // INC A
+            0x04 => { self.inc_r8(RegisterType::B); 4 }                                // INC B
+            0x0C => { self.inc_r8(RegisterType::C); 4 }                                // INC C
+            0x14 => { self.inc_r8(RegisterType::D); 4 }                                // INC D
+            0x1C => { self.inc_r8(RegisterType::E); 4 }                                // INC E
+            0x24 => { self.inc_r8(RegisterType::H); 4 }                                // INC H
+            0x2C => { self.inc_r8(RegisterType::L); 4 }                                // INC L
+            0x34 => { self.inc_r8(RegisterType::HL); 12 }                              // INC [HL]
+            0x3D => { self.dec_r8(RegisterType::A); 4 }                                // DEC A
+            0x05 => { self.dec_r8(RegisterType::B); 4 }                                // DEC B
+            0x0D => { self.dec_r8(RegisterType::C); 4 }                                // DEC C
+            0x15 => { self.dec_r8(RegisterType::D); 4 }                                // DEC D
+            0x1D => { self.dec_r8(RegisterType::E); 4 }                                // DEC E
+            0x25 => { self.dec_r8(RegisterType::H); 4 }                                // DEC H
+            0x2D => { self.dec_r8(RegisterType::L); 4 }                                // DEC L
+            0x35 => { self.dec_r8(RegisterType::HL); 12 }                              // DEC [HL]
+            0x80 => { self.add_a_impl(self.regs.b); 4 }                                // ADD A, B
+            0x81 => { self.add_a_impl(self.regs.c); 4 }                                // ADD A, C
+            0x82 => { self.add_a_impl(self.regs.d); 4 }                                // ADD A, D
+            0x83 => { self.add_a_impl(self.regs.e); 4 }                                // ADD A, E
+            0x84 => { self.add_a_impl(self.regs.h); 4 }                                // ADD A, H
+            0x85 => { self.add_a_impl(self.regs.l); 4 }                                // ADD A, L
             0x86 => {
                 // ADD A, (HL)
-                let address = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let address = self.regs.get_hl();
                 let value = self.mmu.read_byte(address);
-                self.regs.a += value;
-            }
-            0x87 => self.add_a(self.regs.a), // ADD A, A
-            0x88 => self.adc_a(self.regs.b), // ADC A, B
-            0x89 => self.adc_a(self.regs.c), // ADC A, C
-            0x8A => self.adc_a(self.regs.d), // ADC A, D
-            0x8B => self.adc_a(self.regs.e), // ADC A, E
-            0x8C => self.adc_a(self.regs.h), // ADC A, H
-            0x8D => self.adc_a(self.regs.l), // ADC A, L
-            0x90 => self.sub_a(RegisterType::B), // SUB A, B
-            0x91 => self.sub_a(RegisterType::C), // SUB A, C
-            0x92 => self.sub_a(RegisterType::D), // SUB A, D
-            0x93 => self.sub_a(RegisterType::E), // SUB A, E
-            0x94 => self.sub_a(RegisterType::H), // SUB A, H
-            0x95 => self.sub_a(RegisterType::L), // SUB A, L
-            0x96 => self.sub_a(RegisterType::HL), // SUB A, [HL]
-            0x97 => self.sub_a(RegisterType::A), // SUB A, A
+                self.add_a_impl(value);
+                8
+            }
+            0x87 => { self.add_a_impl(self.regs.a); 4 } // ADD A, A
+            0x88 => { self.adc_a(self.regs.b); 4 }      // ADC A, B
+            0x89 => { self.adc_a(self.regs.c); 4 }      // ADC A, C
+            0x8A => { self.adc_a(self.regs.d); 4 }      // ADC A, D
+            0x8B => { self.adc_a(self.regs.e); 4 }      // ADC A, E
+            0x8C => { self.adc_a(self.regs.h); 4 }      // ADC A, H
+            0x8D => { self.adc_a(self.regs.l); 4 }      // ADC A, L
+            0x90 => { self.sub_a(RegisterType::B); 4 }  // SUB A, B
+            0x91 => { self.sub_a(RegisterType::C); 4 }  // SUB A, C
+            0x92 => { self.sub_a(RegisterType::D); 4 }  // SUB A, D
+            0x93 => { self.sub_a(RegisterType::E); 4 }  // SUB A, E
+            0x94 => { self.sub_a(RegisterType::H); 4 }  // SUB A, H
+            0x95 => { self.sub_a(RegisterType::L); 4 }  // SUB A, L
+            0x96 => { self.sub_a(RegisterType::HL); 8 } // SUB A, [HL]
+            0x97 => { self.sub_a(RegisterType::A); 4 }  // SUB A, A
+            0x98 => { self.sbc_a(self.regs.b); 4 }      // SBC A, B
+            0x99 => { self.sbc_a(self.regs.c); 4 }      // SBC A, C
+            0x9A => { self.sbc_a(self.regs.d); 4 }      // SBC A, D
+            0x9B => { self.sbc_a(self.regs.e); 4 }      // SBC A, E
+            0x9C => { self.sbc_a(self.regs.h); 4 }      // SBC A, H
+            0x9D => { self.sbc_a(self.regs.l); 4 }      // SBC A, L
+            0x9E => {
+                // SBC A, [HL]
+                let value = self.read_r8(RegisterType::HL);
+                self.sbc_a(value);
+                8
+            }
+            0x9F => { self.sbc_a(self.regs.a); 4 }      // SBC A, A
+            0xA0 => { self.and_a(self.regs.b); 4 }      // AND A, B
+            0xA1 => { self.and_a(self.regs.c); 4 }      // AND A, C
+            0xA2 => { self.and_a(self.regs.d); 4 }      // AND A, D
+            0xA3 => { self.and_a(self.regs.e); 4 }      // AND A, E
+            0xA4 => { self.and_a(self.regs.h); 4 }      // AND A, H
+            0xA5 => { self.and_a(self.regs.l); 4 }      // AND A, L
+            0xA6 => {
+                // AND A, [HL]
+                let value = self.read_r8(RegisterType::HL);
+                self.and_a(value);
+                8
+            }
+            0xA7 => { self.and_a(self.regs.a); 4 }      // AND A, A
+            0xA8 => { self.xor_a(self.regs.b); 4 }      // XOR A, B
+            0xA9 => { self.xor_a(self.regs.c); 4 }      // XOR A, C
+            0xAA => { self.xor_a(self.regs.d); 4 }      // XOR A, D
+            0xAB => { self.xor_a(self.regs.e); 4 }      // XOR A, E
+            0xAC => { self.xor_a(self.regs.h); 4 }      // XOR A, H
+            0xAD => { self.xor_a(self.regs.l); 4 }      // XOR A, L
+            0xAE => {
+                // XOR A, [HL]
+                let value = self.read_r8(RegisterType::HL);
+                self.xor_a(value);
+                8
+            }
+            0xAF => { self.xor_a(self.regs.a); 4 }      // XOR A, A
+            0xB0 => { self.or_a(self.regs.b); 4 }       // OR A, B
+            0xB1 => { self.or_a(self.regs.c); 4 }       // OR A, C
+            0xB2 => { self.or_a(self.regs.d); 4 }       // OR A, D
+            0xB3 => { self.or_a(self.regs.e); 4 }       // OR A, E
+            0xB4 => { self.or_a(self.regs.h); 4 }       // OR A, H
+            0xB5 => { self.or_a(self.regs.l); 4 }       // OR A, L
+            0xB6 => {
+                // OR A, [HL]
+                let value = self.read_r8(RegisterType::HL);
+                self.or_a(value);
+                8
+            }
+            0xB7 => { self.or_a(self.regs.a); 4 }       // OR A, A
+            0xB8 => { self.cp_a(RegisterType::B); 4 }   // CP A, B
+            0xB9 => { self.cp_a(RegisterType::C); 4 }   // CP A, C
+            0xBA => { self.cp_a(RegisterType::D); 4 }   // CP A, D
+            0xBB => { self.cp_a(RegisterType::E); 4 }   // CP A, E
+            0xBC => { self.cp_a(RegisterType::H); 4 }   // CP A, H
+            0xBD => { self.cp_a(RegisterType::L); 4 }   // CP A, L
+            0xBE => { self.cp_a(RegisterType::HL); 8 }  // CP A, [HL]
+            0xBF => { self.cp_a(RegisterType::A); 4 }   // CP A, A
             0x3E => {
                 let value = self.fetch();
                 self.regs.a = value;
+                8
             } // LD A, n
             0x06 => {
                 let value = self.fetch();
                 self.regs.b = value;
+                8
             } // LD B, n
             0x0E => {
                 let value = self.fetch();
                 self.regs.c = value;
+                8
             } // LD C, n
             0x16 => {
                 let value = self.fetch();
                 self.regs.d = value;
+                8
             } // LD D, n
             0x1E => {
                 let value = self.fetch();
                 self.regs.e = value;
+                8
             } // LD E, n
             0x26 => {
                 let value = self.fetch();
                 self.regs.h = value;
+                8
             } // LD H, n
             0x2E => {
                 let value = self.fetch();
                 self.regs.l = value;
+                8
             } // LD L, n
             0x36 => {
                 // LD [HL], n
-                let addr = ((self.regs.h as u16) << 8) | (self.regs.l as u16);
+                let addr = self.regs.get_hl();
                 let value = self.fetch();
                 self.mmu.write_byte(addr, value);
+                12
             }
-            0x07 => self.rlca(),                                      // RLCA
-            0x0F => self.rrca(),                                      // RRCA
-            0x17 => self.rla(),                                       // RLA
-            0x1F => self.rra(),                                       // RRA
-            0x27 => self.daa(),                                       // DAA
-            0x2F => self.cpl(),                                       // CPL
-            0x37 => self.scf(),                                       // SCF
-            0x3F => self.ccf(),                                       // CCF
-            0x18 => self.jr_e8(),                                     // JR e8
+            0x07 => { self.rlca(); 4 }   // RLCA
+            0x0F => { self.rrca(); 4 }   // RRCA
+            0x17 => { self.rla(); 4 }    // RLA
+            0x1F => { self.rra(); 4 }    // RRA
+            0x27 => { self.daa(); 4 }    // DAA
+            0x2F => { self.cpl(); 4 }    // CPL
+            0x37 => { self.scf(); 4 }    // SCF
+            0x3F => { self.ccf(); 4 }    // CCF
+            0x18 => { self.jr_e8(); 12 } // JR e8
             0x20 => self.jr_cond_e8(ConditionType::NZ),               // JR NZ, e8
             0x28 => self.jr_cond_e8(ConditionType::Z),                // JR Z, e8
             0x30 => self.jr_cond_e8(ConditionType::NC),               // JR NC, e8
             0x38 => self.jr_cond_e8(ConditionType::C),                // JR C, e8
-            0x10 => self.stop(),                                      // STOP
-            0x40 => self.ld_r8_r8(RegisterType::B, RegisterType::B),  // LD B, B
-            0x41 => self.ld_r8_r8(RegisterType::B, RegisterType::C),  // LD B, C
-            0x42 => self.ld_r8_r8(RegisterType::B, RegisterType::D),  // LD B, D
-            0x43 => self.ld_r8_r8(RegisterType::B, RegisterType::E),  // LD B, E
-            0x44 => self.ld_r8_r8(RegisterType::B, RegisterType::H),  // LD B, H
-            0x45 => self.ld_r8_r8(RegisterType::B, RegisterType::L),  // LD B, L
-            0x46 => self.ld_r8_r8(RegisterType::B, RegisterType::HL), // LD B, [HL]
-            0x47 => self.ld_r8_r8(RegisterType::B, RegisterType::A),  // LD B, A
-            0x48 => self.ld_r8_r8(RegisterType::C, RegisterType::B),  // LD C, B
-            0x49 => self.ld_r8_r8(RegisterType::C, RegisterType::C),  // LD C, C
-            0x4A => self.ld_r8_r8(RegisterType::C, RegisterType::D),  // LD C, D
-            0x4B => self.ld_r8_r8(RegisterType::C, RegisterType::E),  // LD C, E
-            0x4C => self.ld_r8_r8(RegisterType::C, RegisterType::H),  // LD C, H
-            0x4D => self.ld_r8_r8(RegisterType::C, RegisterType::L),  // LD C, L
-            0x4E => self.ld_r8_r8(RegisterType::C, RegisterType::HL), // LD C, [HL]
-            0x4F => self.ld_r8_r8(RegisterType::C, RegisterType::A),  // LD C, A
-            0x50 => self.ld_r8_r8(RegisterType::D, RegisterType::B),  // LD D, B
-            0x51 => self.ld_r8_r8(RegisterType::D, RegisterType::C),  // LD D, C
-            0x52 => self.ld_r8_r8(RegisterType::D, RegisterType::D),  // LD D, D
-            0x53 => self.ld_r8_r8(RegisterType::D, RegisterType::E),  // LD D, E
-            0x54 => self.ld_r8_r8(RegisterType::D, RegisterType::H),  // LD D, H
-            0x55 => self.ld_r8_r8(RegisterType::D, RegisterType::L),  // LD D, L
-            0x56 => self.ld_r8_r8(RegisterType::D, RegisterType::HL), // LD D, [HL]
-            0x57 => self.ld_r8_r8(RegisterType::D, RegisterType::A),  // LD D, A
-            0x58 => self.ld_r8_r8(RegisterType::E, RegisterType::B),  // LD E, B
-            0x59 => self.ld_r8_r8(RegisterType::E, RegisterType::C),  // LD E, C
-            0x5A => self.ld_r8_r8(RegisterType::E, RegisterType::D),  // LD E, D
-            0x5B => self.ld_r8_r8(RegisterType::E, RegisterType::E),  // LD E, E
-            0x5C => self.ld_r8_r8(RegisterType::E, RegisterType::H),  // LD E, H
-            0x5D => self.ld_r8_r8(RegisterType::E, RegisterType::L),  // LD E, L
-            0x5E => self.ld_r8_r8(RegisterType::E, RegisterType::HL), // LD E, [HL]
-            0x5F => self.ld_r8_r8(RegisterType::E, RegisterType::A),  // LD E, A
-            0x60 => self.ld_r8_r8(RegisterType::H, RegisterType::B),  // LD H, B
-            0x61 => self.ld_r8_r8(RegisterType::H, RegisterType::C),  // LD H, C
-            0x62 => self.ld_r8_r8(RegisterType::H, RegisterType::D),  // LD H, D
-            0x63 => self.ld_r8_r8(RegisterType::H, RegisterType::E),  // LD H, E
-            0x64 => self.ld_r8_r8(RegisterType::H, RegisterType::H),  // LD H, H
-            0x65 => self.ld_r8_r8(RegisterType::H, RegisterType::L),  // LD H, L
-            0x66 => self.ld_r8_r8(RegisterType::H, RegisterType::HL), // LD H, [HL]
-            0x67 => self.ld_r8_r8(RegisterType::H, RegisterType::A),  // LD H, A
-            0x68 => self.ld_r8_r8(RegisterType::L, RegisterType::B),  // LD L, B
-            0x69 => self.ld_r8_r8(RegisterType::L, RegisterType::C),  // LD L, C
-            0x6A => self.ld_r8_r8(RegisterType::L, RegisterType::D),  // LD L, D
-            0x6B => self.ld_r8_r8(RegisterType::L, RegisterType::E),  // LD L, E
-            0x6C => self.ld_r8_r8(RegisterType::L, RegisterType::H),  // LD L, H
-            0x6D => self.ld_r8_r8(RegisterType::L, RegisterType::L),  // LD L, L
-            0x6E => self.ld_r8_r8(RegisterType::L, RegisterType::HL), // LD L, [HL]
-            0x6F => self.ld_r8_r8(RegisterType::L, RegisterType::A),  // LD L, A
-            0x70 => self.ld_r8_r8(RegisterType::HL, RegisterType::B), // LD [HL], B
-            0x71 => self.ld_r8_r8(RegisterType::HL, RegisterType::C), // LD [HL], C
-            0x72 => self.ld_r8_r8(RegisterType::HL, RegisterType::D), // LD [HL], D
-            0x73 => self.ld_r8_r8(RegisterType::HL, RegisterType::E), // LD [HL], E
-            0x74 => self.ld_r8_r8(RegisterType::HL, RegisterType::H), // LD [HL], H
-            0x75 => self.ld_r8_r8(RegisterType::HL, RegisterType::L), // LD [HL], L
-            0x76 => self.halt(),                                      // HALT
-            0x77 => self.ld_r8_r8(RegisterType::HL, RegisterType::A), // LD [HL], A
-            0x78 => self.ld_r8_r8(RegisterType::A, RegisterType::B),  // LD A, B
-            0x79 => self.ld_r8_r8(RegisterType::A, RegisterType::C),  // LD A, C
-            0x7A => self.ld_r8_r8(RegisterType::A, RegisterType::D),  // LD A, D
-            0x7B => self.ld_r8_r8(RegisterType::A, RegisterType::E),  // LD A, E
-            0x7C => self.ld_r8_r8(RegisterType::A, RegisterType::H),  // LD A, H
-            0x7D => self.ld_r8_r8(RegisterType::A, RegisterType::L),  // LD A, L
-            0x7E => self.ld_r8_r8(RegisterType::A, RegisterType::HL), // LD A, [HL]
-            0x7F => self.ld_r8_r8(RegisterType::A, RegisterType::A),  // LD A, A
+            0x10 => { self.stop(); 4 }                                      // STOP
+            0x40 => { self.ld_r8_r8(RegisterType::B, RegisterType::B); 4 }  // LD B, B
+            0x41 => { self.ld_r8_r8(RegisterType::B, RegisterType::C); 4 }  // LD B, C
+            0x42 => { self.ld_r8_r8(RegisterType::B, RegisterType::D); 4 }  // LD B, D
+            0x43 => { self.ld_r8_r8(RegisterType::B, RegisterType::E); 4 }  // LD B, E
+            0x44 => { self.ld_r8_r8(RegisterType::B, RegisterType::H); 4 }  // LD B, H
+            0x45 => { self.ld_r8_r8(RegisterType::B, RegisterType::L); 4 }  // LD B, L
+            0x46 => { self.ld_r8_r8(RegisterType::B, RegisterType::HL); 8 } // LD B, [HL]
+            0x47 => { self.ld_r8_r8(RegisterType::B, RegisterType::A); 4 }  // LD B, A
+            0x48 => { self.ld_r8_r8(RegisterType::C, RegisterType::B); 4 }  // LD C, B
+            0x49 => { self.ld_r8_r8(RegisterType::C, RegisterType::C); 4 }  // LD C, C
+            0x4A => { self.ld_r8_r8(RegisterType::C, RegisterType::D); 4 }  // LD C, D
+            0x4B => { self.ld_r8_r8(RegisterType::C, RegisterType::E); 4 }  // LD C, E
+            0x4C => { self.ld_r8_r8(RegisterType::C, RegisterType::H); 4 }  // LD C, H
+            0x4D => { self.ld_r8_r8(RegisterType::C, RegisterType::L); 4 }  // LD C, L
+            0x4E => { self.ld_r8_r8(RegisterType::C, RegisterType::HL); 8 } // LD C, [HL]
+            0x4F => { self.ld_r8_r8(RegisterType::C, RegisterType::A); 4 }  // LD C, A
+            0x50 => { self.ld_r8_r8(RegisterType::D, RegisterType::B); 4 }  // LD D, B
+            0x51 => { self.ld_r8_r8(RegisterType::D, RegisterType::C); 4 }  // LD D, C
+            0x52 => { self.ld_r8_r8(RegisterType::D, RegisterType::D); 4 }  // LD D, D
+            0x53 => { self.ld_r8_r8(RegisterType::D, RegisterType::E); 4 }  // LD D, E
+            0x54 => { self.ld_r8_r8(RegisterType::D, RegisterType::H); 4 }  // LD D, H
+            0x55 => { self.ld_r8_r8(RegisterType::D, RegisterType::L); 4 }  // LD D, L
+            0x56 => { self.ld_r8_r8(RegisterType::D, RegisterType::HL); 8 } // LD D, [HL]
+            0x57 => { self.ld_r8_r8(RegisterType::D, RegisterType::A); 4 }  // LD D, A
+            0x58 => { self.ld_r8_r8(RegisterType::E, RegisterType::B); 4 }  // LD E, B
+            0x59 => { self.ld_r8_r8(RegisterType::E, RegisterType::C); 4 }  // LD E, C
+            0x5A => { self.ld_r8_r8(RegisterType::E, RegisterType::D); 4 }  // LD E, D
+            0x5B => { self.ld_r8_r8(RegisterType::E, RegisterType::E); 4 }  // LD E, E
+            0x5C => { self.ld_r8_r8(RegisterType::E, RegisterType::H); 4 }  // LD E, H
+            0x5D => { self.ld_r8_r8(RegisterType::E, RegisterType::L); 4 }  // LD E, L
+            0x5E => { self.ld_r8_r8(RegisterType::E, RegisterType::HL); 8 } // LD E, [HL]
+            0x5F => { self.ld_r8_r8(RegisterType::E, RegisterType::A); 4 }  // LD E, A
+            0x60 => { self.ld_r8_r8(RegisterType::H, RegisterType::B); 4 }  // LD H, B
+            0x61 => { self.ld_r8_r8(RegisterType::H, RegisterType::C); 4 }  // LD H, C
+            0x62 => { self.ld_r8_r8(RegisterType::H, RegisterType::D); 4 }  // LD H, D
+            0x63 => { self.ld_r8_r8(RegisterType::H, RegisterType::E); 4 }  // LD H, E
+            0x64 => { self.ld_r8_r8(RegisterType::H, RegisterType::H); 4 }  // LD H, H
+            0x65 => { self.ld_r8_r8(RegisterType::H, RegisterType::L); 4 }  // LD H, L
+            0x66 => { self.ld_r8_r8(RegisterType::H, RegisterType::HL); 8 } // LD H, [HL]
+            0x67 => { self.ld_r8_r8(RegisterType::H, RegisterType::A); 4 }  // LD H, A
+            0x68 => { self.ld_r8_r8(RegisterType::L, RegisterType::B); 4 }  // LD L, B
+            0x69 => { self.ld_r8_r8(RegisterType::L, RegisterType::C); 4 }  // LD L, C
+            0x6A => { self.ld_r8_r8(RegisterType::L, RegisterType::D); 4 }  // LD L, D
+            0x6B => { self.ld_r8_r8(RegisterType::L, RegisterType::E); 4 }  // LD L, E
+            0x6C => { self.ld_r8_r8(RegisterType::L, RegisterType::H); 4 }  // LD L, H
+            0x6D => { self.ld_r8_r8(RegisterType::L, RegisterType::L); 4 }  // LD L, L
+            0x6E => { self.ld_r8_r8(RegisterType::L, RegisterType::HL); 8 } // LD L, [HL]
+            0x6F => { self.ld_r8_r8(RegisterType::L, RegisterType::A); 4 }  // LD L, A
+            0x70 => { self.ld_r8_r8(RegisterType::HL, RegisterType::B); 8 } // LD [HL], B
+            0x71 => { self.ld_r8_r8(RegisterType::HL, RegisterType::C); 8 } // LD [HL], C
+            0x72 => { self.ld_r8_r8(RegisterType::HL, RegisterType::D); 8 } // LD [HL], D
+            0x73 => { self.ld_r8_r8(RegisterType::HL, RegisterType::E); 8 } // LD [HL], E
+            0x74 => { self.ld_r8_r8(RegisterType::HL, RegisterType::H); 8 } // LD [HL], H
+            0x75 => { self.ld_r8_r8(RegisterType::HL, RegisterType::L); 8 } // LD [HL], L
+            0x76 => { self.halt(); 4 }                                      // HALT
+            0x77 => { self.ld_r8_r8(RegisterType::HL, RegisterType::A); 8 } // LD [HL], A
+            0x78 => { self.ld_r8_r8(RegisterType::A, RegisterType::B); 4 }  // LD A, B
+            0x79 => { self.ld_r8_r8(RegisterType::A, RegisterType::C); 4 }  // LD A, C
+            0x7A => { self.ld_r8_r8(RegisterType::A, RegisterType::D); 4 }  // LD A, D
+            0x7B => { self.ld_r8_r8(RegisterType::A, RegisterType::E); 4 }  // LD A, E
+            0x7C => { self.ld_r8_r8(RegisterType::A, RegisterType::H); 4 }  // LD A, H
+            0x7D => { self.ld_r8_r8(RegisterType::A, RegisterType::L); 4 }  // LD A, L
+            0x7E => { self.ld_r8_r8(RegisterType::A, RegisterType::HL); 8 } // LD A, [HL]
+            0x7F => { self.ld_r8_r8(RegisterType::A, RegisterType::A); 4 }  // LD A, A
+            0xE8 => { self.regs.sp = self.add_sp_e8(); 16 }                 // ADD SP, e8
+            0xF8 => {
+                // LD HL, SP+e8 (SPは変更しない)
+                let value = self.add_sp_e8();
+                self.regs.set_hl(value);
+                12
+            }
+            0xCB => {
+                // CB prefix
+                let cb_opcode = self.fetch();
+                self.execute_cb(cb_opcode)
+            }
+            0xC2 => {
+                // JP NZ, nn
+                let addr = self.fetch_u16();
+                self.jp_cond(ConditionType::NZ, addr)
+            }
+            0xCA => {
+                // JP Z, nn
+                let addr = self.fetch_u16();
+                self.jp_cond(ConditionType::Z, addr)
+            }
+            0xD2 => {
+                // JP NC, nn
+                let addr = self.fetch_u16();
+                self.jp_cond(ConditionType::NC, addr)
+            }
+            0xDA => {
+                // JP C, nn
+                let addr = self.fetch_u16();
+                self.jp_cond(ConditionType::C, addr)
+            }
+            0xE9 => {
+                // JP HL (HLの指す先ではなくHLの値へジャンプ)
+                self.regs.pc = self.regs.get_hl();
+                4
+            }
+            0xF9 => {
+                // LD SP, HL
+                self.regs.sp = self.regs.get_hl();
+                8
+            }
+            0xFB => { self.ime_pending = true; 4 }           // EI (1命令遅れて有効)
+            0xF3 => {
+                // DI (即座に無効)
+                self.ime = false;
+                self.ime_pending = false;
+                4
+            }
             0xC3 => {
                 // JP nn (絶対ジャンプ)
                 let low = self.fetch();
                 let high = self.fetch();
                 self.regs.pc = ((high as u16) << 8) | (low as u16);
+                16
             }
+            0xCD => self.call_nn(),                                  // CALL nn
+            0xC4 => self.call_cond(ConditionType::NZ),               // CALL NZ, nn
+            0xCC => self.call_cond(ConditionType::Z),                // CALL Z, nn
+            0xD4 => self.call_cond(ConditionType::NC),               // CALL NC, nn
+            0xDC => self.call_cond(ConditionType::C),                // CALL C, nn
+            0xC9 => self.ret(),                                      // RET
+            0xD9 => self.reti(),                                     // RETI
+            0xC0 => self.ret_cond(ConditionType::NZ),                // RET NZ
+            0xC8 => self.ret_cond(ConditionType::Z),                 // RET Z
+            0xD0 => self.ret_cond(ConditionType::NC),                // RET NC
+            0xD8 => self.ret_cond(ConditionType::C),                 // RET C
+            0xC7 => self.rst(0x00),                                  // RST 00H
+            0xCF => self.rst(0x08),                                  // RST 08H
+            0xD7 => self.rst(0x10),                                  // RST 10H
+            0xDF => self.rst(0x18),                                  // RST 18H
+            0xE7 => self.rst(0x20),                                  // RST 20H
+            0xEF => self.rst(0x28),                                  // RST 28H
+            0xF7 => self.rst(0x30),                                  // RST 30H
+            0xFF => self.rst(0x38),                                  // RST 38H
             0xC6 => {
                 // ADD A, n
                 let value = self.fetch();
-                self.regs.a += value;
+                self.add_a_impl(value);
+                8
             }
             0xD6 => {
                 // SUB A, n
                 let value = self.fetch();
-                self.regs.a -= value;
+                self.alu_sub(value, true);
+                8
             }
             0x01 => {
                 // LD BC, nn
@@ -730,6 +1366,7 @@ impl CPU {
                 let high = self.fetch();
                 self.regs.b = high;
                 self.regs.c = low;
+                12
             }
             0x11 => {
                 // LD DE, nn
@@ -737,6 +1374,7 @@ impl CPU {
                 let high = self.fetch();
                 self.regs.d = high;
                 self.regs.e = low;
+                12
             }
             0x21 => {
                 // LD HL, nn
@@ -744,23 +1382,53 @@ impl CPU {
                 let high = self.fetch();
                 self.regs.h = high;
                 self.regs.l = low;
+                12
             }
             0x31 => {
                 // LD SP, nn
                 let low = self.fetch();
                 let high = self.fetch();
                 self.regs.sp = u16::from_le_bytes([low, high]);
+                12
+            }
+            0xC5 => { self.push_r16(self.regs.b, self.regs.c); 16 } // PUSH BC
+            0xD5 => { self.push_r16(self.regs.d, self.regs.e); 16 } // PUSH DE
+            0xE5 => { self.push_r16(self.regs.h, self.regs.l); 16 } // PUSH HL
+            0xF5 => { self.push_r16(self.regs.a, self.regs.f); 16 } // PUSH AF
+            0xC1 => {
+                // POP BC
+                (self.regs.b, self.regs.c) = self.pop_r16();
+                12
+            }
+            0xD1 => {
+                // POP DE
+                (self.regs.d, self.regs.e) = self.pop_r16();
+                12
+            }
+            0xE1 => {
+                // POP HL
+                (self.regs.h, self.regs.l) = self.pop_r16();
+                12
+            }
+            0xF1 => {
+                // POP AF (Fの下位4bitは0になる)
+                let (a, f) = self.pop_r16();
+                self.regs.a = a;
+                self.regs.f = f & 0xF0;
+                12
             }
             0xE2 => {
                 // LDH (C), A
                 let addr = 0xFF00 | (self.regs.c as u16);
                 self.mmu.write_byte(addr, self.regs.a);
+                8
             }
             0xE0 => {
                 // LDH (n), A
                 let offset = self.fetch();
                 let addr = 0xFF00 | (offset as u16); // 0xFF00 + n
                 self.mmu.write_byte(addr, self.regs.a);
+                12
             }
             0xEA => {
                 // LDH (nn), A
@@ -768,16 +1436,1054 @@ impl CPU {
                 let high = self.fetch();
                 let addr = u16::from_le_bytes([low, high]);
                 self.mmu.write_byte(addr, self.regs.a);
+                16
+            }
+            0xF0 => {
+                // LDH A, (n)
+                let offset = self.fetch();
+                let addr = 0xFF00 | (offset as u16); // 0xFF00 + n
+                self.regs.a = self.mmu.read_byte(addr);
+                12
+            }
+            0xFA => {
+                // LD A, (nn)
+                let low = self.fetch();
+                let high = self.fetch();
+                let addr = u16::from_le_bytes([low, high]);
+                self.regs.a = self.mmu.read_byte(addr);
+                16
             }
             0xF2 => {
                 // LDH A, (C)
                 let addr = 0xFF00 | (self.regs.c as u16);
                 let value = self.mmu.read_byte(addr);
                 self.regs.a = value;
+                8
             }
             _ => {
                 eprintln!("未実装の命令: 0x{:02X}", opcode);
+                4
             }
         }
     }
 }
+
+// 1命令ずつ実行して状態を返す (終わりはない)
+// 例: cpu.by_ref().take_while(|s| s.pc != 0x0150)
+impl Iterator for CPU {
+    type Item = CpuSnapshot;
+
+    fn next(&mut self) -> Option<CpuSnapshot> {
+        self.step();
+        Some(self.snapshot())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::joypad::JoypadState;
+    use crate::mmu::AccessKind;
+
+    // 0x0100 から program を配置した CPU を作る
+    fn setup(program: &[u8]) -> CPU {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0100..0x0100 + program.len()].copy_from_slice(program);
+        CPU::new(MMU::new(rom))
+    }
+
+    #[test]
+    fn panic_hook_records_state_before_each_instruction() {
+        let mut cpu = setup(&[0x3C; 0x20]); // INC A x 32
+        cpu.step(); // 登録前は記録しない
+        CRASH_STATE.with(|state| assert!(state.borrow().is_none()));
+
+        cpu.install_panic_hook();
+        cpu.install_panic_hook(); // 2回目はフックを登録し直さない
+        for _ in 0..PC_HISTORY_LEN + 2 {
+            cpu.step();
+        }
+        CRASH_STATE.with(|state| {
+            let state = state.borrow();
+            let state = state.as_ref().unwrap();
+            assert_eq!(state.snapshot.pc, 0x0112); // 最後に実行した INC A
+            assert_eq!(state.snapshot.a, 0x13);
+            assert_eq!(state.pc_history.len(), PC_HISTORY_LEN);
+            assert_eq!(state.pc_history.front(), Some(&0x0103));
+            assert_eq!(state.pc_history.back(), Some(&0x0112));
+        });
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn save_state_round_trip() {
+        // LD A, 0x42; LD (0xC000), A; LD (0x8000), A
+        let mut cpu = setup(&[0x3E, 0x42, 0xEA, 0x00, 0xC0, 0xEA, 0x00, 0x80]);
+        for _ in 0..3 {
+            cpu.step();
+        }
+        cpu.regs.set_hl(0xBEEF);
+
+        let json = serde_json::to_string(&cpu).unwrap();
+        let mut loaded: CPU = serde_json::from_str(&json).unwrap();
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0100] = 0x3E;
+        loaded.mmu.reload_rom(rom);
+
+        assert_eq!(loaded.regs, cpu.regs);
+        assert_eq!(loaded.cycles, cpu.cycles);
+        assert_eq!(loaded.mmu.read_byte(0xC000), 0x42); // WRAM
+        assert_eq!(loaded.mmu.read_byte(0x8000), 0x42); // VRAM
+        assert_eq!(loaded.mmu.read_byte(0x0100), 0x3E); // ROMは差し戻したもの
+    }
+
+    #[test]
+    fn step_advances_oam_dma() {
+        let mut cpu = setup(&[]); // NOP (1 Mサイクル) が続く
+        cpu.mmu.write_byte(0xFF46, 0xC0);
+        for _ in 0..0x9F {
+            cpu.step();
+        }
+        assert!(cpu.mmu.in_oam_dma());
+        cpu.step();
+        assert!(!cpu.mmu.in_oam_dma());
+    }
+
+    #[test]
+    fn coverage_marks_fetched_bytes() {
+        let mut cpu = setup(&[0x3E, 0x12, 0x00]); // LD A, 0x12; NOP
+        cpu.step(); // 無効の間は記録しない
+        assert!(!cpu.coverage.is_visited(0x0100));
+
+        cpu.regs.pc = 0x0100;
+        cpu.coverage_enabled = true;
+        cpu.step();
+        cpu.step();
+        assert!(cpu.coverage.is_visited(0x0100));
+        assert!(cpu.coverage.is_visited(0x0101)); // 即値のバイトも記録される
+        assert!(cpu.coverage.is_visited(0x0102));
+        assert!(!cpu.coverage.is_visited(0x0103));
+    }
+
+    #[test]
+    fn af_masks_low_nibble_of_f() {
+        let mut regs = Registers::new();
+        regs.set_af(0x12FF);
+        assert_eq!((regs.a, regs.f), (0x12, 0xF0));
+        assert_eq!(regs.get_af(), 0x12F0);
+        assert_eq!(regs.af(), regs.get_af());
+    }
+
+    #[test]
+    fn registers_match_dmg_post_boot_state() {
+        let regs = Registers::new();
+        assert_eq!(regs.a, 0x01);
+        assert_eq!(regs.f, 0xB0); // Z=1, N=0, H=1, C=1
+        assert_eq!(regs.b, 0x00);
+        assert_eq!(regs.c, 0x13);
+        assert_eq!(regs.d, 0x00);
+        assert_eq!(regs.e, 0xD8);
+        assert_eq!(regs.h, 0x01);
+        assert_eq!(regs.l, 0x4D);
+        assert_eq!(regs.sp, 0xFFFE);
+        assert_eq!(regs.pc, 0x0100);
+    }
+
+    #[test]
+    fn single_step_callback_runs_once_per_instruction() {
+        // JR +1; NOP; JR NZ, -4 (Z=1なので分岐しない); INC A
+        let mut cpu = setup(&[0x18, 0x01, 0x00, 0x20, 0xFC, 0x3C]);
+        let mut pcs = Vec::new();
+        for _ in 0..3 {
+            cpu.single_step_with_callback(|cpu| pcs.push(cpu.regs.pc));
+        }
+        assert_eq!(pcs, vec![0x0103, 0x0105, 0x0106]);
+    }
+
+    #[test]
+    fn interrupt_vectors_are_in_priority_order() {
+        let vectors = CPU::interrupt_vectors();
+        for (i, &(bit, addr)) in vectors.iter().enumerate() {
+            assert_eq!(bit, 1 << i);
+            assert_eq!(addr, 0x0040 + 8 * i as u16);
+        }
+    }
+
+    #[test]
+    fn step_accumulates_t_cycles() {
+        // NOP; LD A, n; JR Z, +0 (分岐する); JR NZ, +0 (分岐しない); PUSH BC
+        let mut cpu = setup(&[0x00, 0x3E, 0x12, 0x28, 0x00, 0x20, 0x00, 0xC5]);
+        let mut expected = 0;
+        for cycles in [4, 8, 12, 8, 16] {
+            cpu.step();
+            expected += cycles;
+            assert_eq!(cpu.cycles(), expected);
+        }
+    }
+
+    #[test]
+    fn ld_hl_n_writes_wram() {
+        let mut cpu = setup(&[0x36, 0x5A]); // LD [HL], 0x5A
+        cpu.regs.set_hl(0xC123);
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xC123), 0x5A);
+        assert_eq!(cpu.regs.pc, 0x0102);
+    }
+
+    #[test]
+    fn add_a_sets_flags() {
+        // (A, B, 結果, F)
+        for (a, b, result, f) in [
+            (0x01, 0x02, 0x03, 0x00),
+            (0x0F, 0x01, 0x10, 0x20), // H
+            (0xF0, 0x20, 0x10, 0x10), // C
+            (0xFF, 0x01, 0x00, 0xB0), // Z, H, C
+            (0x00, 0x00, 0x00, 0x80), // Z
+        ] {
+            let mut cpu = setup(&[0x80]); // ADD A, B
+            cpu.regs.a = a;
+            cpu.regs.b = b;
+            cpu.regs.f = 0x40; // N は必ずクリアされる
+            cpu.step();
+            assert_eq!((cpu.regs.a, cpu.regs.f), (result, f), "{:02X} + {:02X}", a, b);
+        }
+    }
+
+    #[test]
+    fn add_a_n_and_hl_set_flags() {
+        let mut cpu = setup(&[0xC6, 0x01, 0x86]); // ADD A, 0x01; ADD A, (HL)
+        cpu.regs.a = 0xFF;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x00, 0xB0));
+
+        cpu.mmu.write_byte(0xC000, 0x0F);
+        cpu.regs.set_hl(0xC000);
+        cpu.regs.a = 0x01;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x10, 0x20));
+    }
+
+    #[test]
+    fn dec_r8_sets_half_borrow() {
+        // (B, 結果, F)  C は変化しない
+        for (value, result, f) in [
+            (0x00, 0xFF, 0x60), // N, H
+            (0x10, 0x0F, 0x60), // N, H
+            (0x11, 0x10, 0x40), // N
+            (0xFF, 0xFE, 0x40), // N
+            (0x01, 0x00, 0xC0), // Z, N
+        ] {
+            let mut cpu = setup(&[0x05]); // DEC B
+            cpu.regs.b = value;
+            cpu.regs.f = 0x00;
+            cpu.step();
+            assert_eq!((cpu.regs.b, cpu.regs.f), (result, f), "DEC {:02X}", value);
+        }
+
+        let mut cpu = setup(&[0x05]);
+        cpu.regs.b = 0x11;
+        cpu.regs.f = 0x10;
+        cpu.step();
+        assert_eq!(cpu.regs.f, 0x50); // C は保持
+    }
+
+    #[test]
+    fn iterator_yields_snapshot_after_each_step() {
+        let mut cpu = setup(&[0x3C, 0x3C, 0x00, 0x00]); // INC A; INC A; NOP; NOP
+        let trace: Vec<CpuSnapshot> = cpu.by_ref().take(2).collect();
+        assert_eq!(trace.len(), 2);
+        assert_eq!((trace[0].pc, trace[0].a, trace[0].cycles), (0x0101, 0x02, 4));
+        assert_eq!((trace[1].pc, trace[1].a, trace[1].cycles), (0x0102, 0x03, 8));
+
+        let last = cpu.take_while(|s| s.pc != 0x0104).last().unwrap();
+        assert_eq!(last.pc, 0x0103);
+    }
+
+    #[test]
+    fn sub_and_cp_set_same_flags() {
+        let mut sub = setup(&[0x90]); // SUB A, B
+        let mut cp = setup(&[0xB8]); // CP A, B
+        for cpu in [&mut sub, &mut cp] {
+            cpu.regs.a = 0x10;
+            cpu.regs.b = 0x21;
+            cpu.step();
+        }
+        assert_eq!(sub.regs.a, 0xEF);
+        assert_eq!(cp.regs.a, 0x10); // CP は A を変更しない
+        assert_eq!(sub.regs.f, 0x70); // N, H, C
+        assert_eq!(cp.regs.f, sub.regs.f);
+    }
+
+    #[test]
+    fn cp_a_equal_sets_z_and_keeps_a() {
+        let mut cpu = setup(&[0xBE, 0xBF]); // CP A, [HL]; CP A, A
+        cpu.mmu.write_byte(0xC000, 0x42);
+        cpu.regs.set_hl(0xC000);
+        cpu.regs.a = 0x42;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x42, 0xC0)); // Z, N
+        cpu.regs.f = 0x30;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x42, 0xC0));
+    }
+
+    #[test]
+    fn sub_a_sets_flags() {
+        // (A, B, 結果, F)
+        for (a, b, result, f) in [
+            (0x3E, 0x3E, 0x00, 0xC0), // Z, N
+            (0x3E, 0x0F, 0x2F, 0x60), // N, H
+            (0x3E, 0x40, 0xFE, 0x50), // N, C
+            (0x3E, 0x01, 0x3D, 0x40), // N
+        ] {
+            let mut cpu = setup(&[0x90]); // SUB A, B
+            cpu.regs.a = a;
+            cpu.regs.b = b;
+            cpu.step();
+            assert_eq!((cpu.regs.a, cpu.regs.f), (result, f), "{:02X} - {:02X}", a, b);
+        }
+    }
+
+    #[test]
+    fn sub_a_hl_and_sub_a_a() {
+        let mut cpu = setup(&[0x96, 0x97]); // SUB A, [HL]; SUB A, A
+        cpu.mmu.write_byte(0xC000, 0x01);
+        cpu.regs.set_hl(0xC000);
+        cpu.regs.a = 0x10;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x0F, 0x60));
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x00, 0xC0));
+    }
+
+    #[test]
+    fn sbc_a_sets_flags() {
+        // (A, B, キャリー入力, 結果, F)
+        for (a, b, carry, result, f) in [
+            (0x10, 0x0F, true, 0x00, 0xE0), // Z, N, H
+            (0x12, 0x01, false, 0x11, 0x40), // N
+            (0x10, 0x00, true, 0x0F, 0x60), // N, H (キャリーで下位4bitが借りる)
+            (0x00, 0x00, true, 0xFF, 0x70), // N, H, C
+            (0x20, 0x30, false, 0xF0, 0x50), // N, C
+        ] {
+            let mut cpu = setup(&[0x98]); // SBC A, B
+            cpu.regs.a = a;
+            cpu.regs.b = b;
+            cpu.regs.f = if carry { 0x10 } else { 0x00 };
+            cpu.step();
+            assert_eq!((cpu.regs.a, cpu.regs.f), (result, f), "{:02X} - {:02X} - {}", a, b, carry);
+        }
+    }
+
+    #[test]
+    fn sbc_a_a_with_carry_is_ff() {
+        let mut cpu = setup(&[0x9F]); // SBC A, A
+        cpu.regs.a = 0x42;
+        cpu.regs.f = 0x10;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0xFF, 0x70));
+    }
+
+    #[test]
+    fn sbc_a_hl() {
+        let mut cpu = setup(&[0x9E]); // SBC A, [HL]
+        cpu.mmu.write_byte(0xC000, 0x05);
+        cpu.regs.set_hl(0xC000);
+        cpu.regs.a = 0x08;
+        cpu.regs.f = 0x10;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x02, 0x40));
+    }
+
+    #[test]
+    fn and_a_always_sets_h() {
+        let mut cpu = setup(&[0xA0, 0xA6]); // AND A, B; AND A, [HL]
+        cpu.regs.a = 0xF0;
+        cpu.regs.b = 0x3C;
+        cpu.regs.f = 0x50;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x30, 0x20));
+
+        cpu.mmu.write_byte(0xC000, 0x0F);
+        cpu.regs.set_hl(0xC000);
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x00, 0xA0)); // Z, H
+    }
+
+    #[test]
+    fn xor_a_a_zeroes_a() {
+        let mut cpu = setup(&[0xAF]); // XOR A, A
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x00, 0x80)); // 初期値 A=0x01, F=0xB0
+    }
+
+    #[test]
+    fn xor_a_clears_n_h_c() {
+        let mut cpu = setup(&[0xA8, 0xAE]); // XOR A, B; XOR A, [HL]
+        cpu.regs.a = 0xF0;
+        cpu.regs.b = 0x3C;
+        cpu.regs.f = 0x70;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0xCC, 0x00));
+
+        cpu.mmu.write_byte(0xC000, 0xCC);
+        cpu.regs.set_hl(0xC000);
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x00, 0x80));
+    }
+
+    #[test]
+    fn or_a_sets_only_z() {
+        // (A, B, 結果, F)
+        for (a, b, result, f) in [
+            (0x00, 0x00, 0x00, 0x80), // 両方0なら Z
+            (0x00, 0x01, 0x01, 0x00),
+            (0xF0, 0x0F, 0xFF, 0x00),
+            (0x5A, 0x5A, 0x5A, 0x00),
+        ] {
+            let mut cpu = setup(&[0xB0]); // OR A, B
+            cpu.regs.a = a;
+            cpu.regs.b = b;
+            cpu.regs.f = 0x70;
+            cpu.step();
+            assert_eq!((cpu.regs.a, cpu.regs.f), (result, f), "{:02X} | {:02X}", a, b);
+        }
+    }
+
+    #[test]
+    fn or_a_hl() {
+        let mut cpu = setup(&[0xB6]); // OR A, [HL]
+        cpu.mmu.write_byte(0xC000, 0x80);
+        cpu.regs.set_hl(0xC000);
+        cpu.regs.a = 0x01;
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.f), (0x81, 0x00));
+    }
+
+    #[test]
+    fn cb_decodes_register_operand() {
+        // SWAP B, C, D, E, H, L, [HL], A
+        let program: Vec<u8> = (0x30..=0x37).flat_map(|op| [0xCB, op]).collect();
+        let mut cpu = setup(&program);
+        cpu.regs.b = 0x12;
+        cpu.regs.c = 0x34;
+        cpu.regs.d = 0x56;
+        cpu.regs.e = 0x78;
+        cpu.regs.set_hl(0xCCCC); // SWAP しても同じアドレス
+        cpu.mmu.write_byte(0xCCCC, 0x9A);
+        cpu.regs.a = 0xBC;
+        let mut cycles = Vec::new();
+        for _ in 0..8 {
+            let before = cpu.cycles;
+            cpu.step();
+            cycles.push(cpu.cycles - before);
+        }
+        assert_eq!(cpu.regs.b, 0x21);
+        assert_eq!(cpu.regs.c, 0x43);
+        assert_eq!(cpu.regs.d, 0x65);
+        assert_eq!(cpu.regs.e, 0x87);
+        assert_eq!(cpu.mmu.read_byte(0xCCCC), 0xA9);
+        assert_eq!(cpu.regs.a, 0xCB);
+        assert_eq!(cycles, vec![8, 8, 8, 8, 8, 8, 16, 8]);
+    }
+
+    #[test]
+    fn cb_rotate_zero_sets_z() {
+        // RLC B, RRC B, RL B, RR B (キャリーなし)
+        for op in [0x00, 0x08, 0x10, 0x18] {
+            let mut cpu = setup(&[0xCB, op]);
+            cpu.regs.b = 0x00;
+            cpu.regs.f = 0x00;
+            cpu.step();
+            assert_eq!((cpu.regs.b, cpu.regs.f), (0x00, 0x80), "CB {:02X}", op);
+        }
+    }
+
+    #[test]
+    fn cb_rl_rr_carry_round_trip() {
+        let mut cpu = setup(&[0xCB, 0x10, 0xCB, 0x18]); // RL B; RR B
+        cpu.regs.b = 0x80;
+        cpu.regs.f = 0x00;
+        cpu.step();
+        assert_eq!((cpu.regs.b, cpu.regs.f), (0x00, 0x90)); // bit7 -> C
+        cpu.step();
+        assert_eq!((cpu.regs.b, cpu.regs.f), (0x80, 0x00)); // C -> bit7
+    }
+
+    #[test]
+    fn cb_rlc_rrc_rotate_through_bit() {
+        let mut cpu = setup(&[0xCB, 0x00, 0xCB, 0x08]); // RLC B; RRC B
+        cpu.regs.b = 0x81;
+        cpu.step();
+        assert_eq!((cpu.regs.b, cpu.regs.f), (0x03, 0x10));
+        cpu.step();
+        assert_eq!((cpu.regs.b, cpu.regs.f), (0x81, 0x10));
+    }
+
+    #[test]
+    fn cb_shift_and_swap() {
+        // (CB opcode, B, 結果, F)
+        for (op, value, result, f) in [
+            (0x20, 0x81, 0x02, 0x10), // SLA: bit7 -> C, 0 -> bit0
+            (0x28, 0x81, 0xC0, 0x10), // SRA: bit7 は保持
+            (0x30, 0xF1, 0x1F, 0x00), // SWAP
+            (0x30, 0x00, 0x00, 0x80), // SWAP 0x00: Z のみ
+            (0x38, 0x81, 0x40, 0x10), // SRL: 0 -> bit7
+            (0x38, 0x01, 0x00, 0x90), // SRL: Z, C
+        ] {
+            let mut cpu = setup(&[0xCB, op]);
+            cpu.regs.b = value;
+            cpu.regs.f = 0x70;
+            cpu.step();
+            assert_eq!((cpu.regs.b, cpu.regs.f), (result, f), "CB {:02X} {:02X}", op, value);
+        }
+    }
+
+    #[test]
+    fn cb_bit_sets_z_h_and_keeps_c() {
+        // (CB opcode, B, F入力, F)
+        for (op, value, f_in, f) in [
+            (0x40, 0x00, 0x50, 0xB0), // BIT 0, B: 0 -> Z, H, C保持
+            (0x40, 0x01, 0x40, 0x20), // BIT 0, B: 1 -> H のみ
+            (0x78, 0x80, 0x00, 0x20), // BIT 7, B
+            (0x78, 0x7F, 0xC0, 0xA0), // BIT 7, B: 0 -> Z, H
+        ] {
+            let mut cpu = setup(&[0xCB, op]);
+            cpu.regs.b = value;
+            cpu.regs.f = f_in;
+            cpu.step();
+            assert_eq!(cpu.regs.f, f, "CB {:02X} {:02X}", op, value);
+            assert_eq!(cpu.regs.b, value);
+        }
+    }
+
+    #[test]
+    fn cb_bit_hl_does_not_write_back() {
+        let mut cpu = setup(&[0xCB, 0x5E]); // BIT 3, [HL]
+        cpu.mmu.write_byte(0xC000, 0x08);
+        cpu.regs.set_hl(0xC000);
+        cpu.mmu.enable_access_log(8);
+        cpu.step();
+        assert_eq!(cpu.regs.f & 0xE0, 0x20);
+        assert_eq!(cpu.cycles, 12);
+        assert!(cpu.mmu.drain_log().iter().all(|e| e.kind == AccessKind::Read));
+    }
+
+    #[test]
+    fn cb_res_set_are_idempotent() {
+        // SET 3, B; SET 3, B; RES 3, B; RES 3, B
+        let mut cpu = setup(&[0xCB, 0xD8, 0xCB, 0xD8, 0xCB, 0x98, 0xCB, 0x98]);
+        cpu.regs.b = 0x00;
+        cpu.regs.f = 0xF0;
+        for expected in [0x08, 0x08, 0x00, 0x00] {
+            cpu.step();
+            assert_eq!(cpu.regs.b, expected);
+            assert_eq!(cpu.regs.f, 0xF0); // フラグは変化しない
+        }
+    }
+
+    #[test]
+    fn cb_res_set_hl_write_back() {
+        let mut cpu = setup(&[0xCB, 0xFE, 0xCB, 0x86]); // SET 7, [HL]; RES 0, [HL]
+        cpu.mmu.write_byte(0xC000, 0x01);
+        cpu.regs.set_hl(0xC000);
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xC000), 0x81);
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xC000), 0x80);
+        assert_eq!(cpu.cycles, 32);
+    }
+
+    #[test]
+    fn push_pop_all_pairs() {
+        // PUSH BC; PUSH DE; PUSH HL; POP BC; POP DE; POP HL
+        let mut cpu = setup(&[0xC5, 0xD5, 0xE5, 0xC1, 0xD1, 0xE1]);
+        cpu.regs.sp = 0xD000;
+        (cpu.regs.b, cpu.regs.c) = (0x11, 0x22);
+        (cpu.regs.d, cpu.regs.e) = (0x33, 0x44);
+        (cpu.regs.h, cpu.regs.l) = (0x55, 0x66);
+        for _ in 0..3 {
+            cpu.step();
+        }
+        assert_eq!(cpu.regs.sp, 0xCFFA);
+        assert_eq!(cpu.mmu.read_byte(0xCFFF), 0x11); // 上位バイトが SP-1
+        assert_eq!(cpu.mmu.read_byte(0xCFFE), 0x22);
+        for _ in 0..3 {
+            cpu.step();
+        }
+        // 積んだ順と逆に取り出すので BC と HL が入れ替わる
+        assert_eq!((cpu.regs.b, cpu.regs.c), (0x55, 0x66));
+        assert_eq!((cpu.regs.d, cpu.regs.e), (0x33, 0x44));
+        assert_eq!((cpu.regs.h, cpu.regs.l), (0x11, 0x22));
+        assert_eq!(cpu.regs.sp, 0xD000);
+    }
+
+    #[test]
+    fn rst_pushes_return_address() {
+        for (i, op) in [0xC7, 0xCF, 0xD7, 0xDF, 0xE7, 0xEF, 0xF7, 0xFF].into_iter().enumerate() {
+            let mut cpu = setup(&[op]);
+            cpu.regs.sp = 0xD000;
+            cpu.step();
+            assert_eq!(cpu.regs.pc, 8 * i as u16, "RST {:02X}", op);
+            assert_eq!(cpu.regs.sp, 0xCFFE);
+            assert_eq!(cpu.mmu.read_word(0xCFFE), 0x0101);
+            assert_eq!(cpu.cycles, 16);
+        }
+    }
+
+    #[test]
+    fn jp_cond_always_consumes_operand() {
+        // JP NZ, 0x1234 (分岐しない); JP Z, 0x5678 (分岐する)
+        let mut cpu = setup(&[0xC2, 0x34, 0x12, 0xCA, 0x78, 0x56]);
+        cpu.regs.f = 0x80;
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.cycles), (0x0103, 12));
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.cycles), (0x5678, 28));
+    }
+
+    #[test]
+    fn jp_nc_c_follow_carry() {
+        for (op, f, pc) in [(0xD2, 0x00, 0x4000), (0xD2, 0x10, 0x0103), (0xDA, 0x10, 0x4000), (0xDA, 0x00, 0x0103)] {
+            let mut cpu = setup(&[op, 0x00, 0x40]);
+            cpu.regs.f = f;
+            cpu.step();
+            assert_eq!(cpu.regs.pc, pc, "{:02X} F={:02X}", op, f);
+        }
+    }
+
+    #[test]
+    fn jp_hl_and_ld_sp_hl() {
+        let mut cpu = setup(&[0xF9, 0xE9]); // LD SP, HL; JP HL
+        cpu.regs.set_hl(0xC123);
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0xC123);
+        cpu.step();
+        assert_eq!(cpu.regs.pc, 0xC123);
+        assert_eq!(cpu.cycles, 8 + 4);
+    }
+
+    #[test]
+    fn ei_enables_ime_after_next_instruction() {
+        let mut cpu = setup(&[0xFB, 0x00, 0x00]); // EI; NOP; NOP
+        cpu.ime = false;
+        cpu.step();
+        assert!(!cpu.ime); // EI の直後はまだ無効
+        cpu.step();
+        assert!(cpu.ime);
+        assert!(!cpu.ime_pending);
+    }
+
+    #[test]
+    fn ei_then_di_leaves_ime_cleared() {
+        let mut cpu = setup(&[0xFB, 0xF3, 0x00]); // EI; DI; NOP
+        cpu.ime = false;
+        for _ in 0..3 {
+            cpu.step();
+            assert!(!cpu.ime);
+        }
+    }
+
+    #[test]
+    fn interrupt_dispatches_highest_priority_first() {
+        let mut cpu = setup(&[0x00]);
+        cpu.regs.sp = 0xD000;
+        cpu.mmu.write_byte(0xFFFF, 0x1F);
+        cpu.mmu.write_byte(0xFF0F, 0x14); // Timer, Joypad
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.regs.sp, cpu.cycles), (0x0050, 0xCFFE, 20));
+        assert_eq!(cpu.mmu.read_word(0xCFFE), 0x0100);
+        assert_eq!(cpu.mmu.read_byte(0xFF0F), 0x10); // Timer だけ落ちる
+        assert!(!cpu.ime);
+    }
+
+    #[test]
+    fn interrupt_ignored_while_ime_cleared() {
+        let mut cpu = setup(&[0x00]);
+        cpu.ime = false;
+        cpu.mmu.write_byte(0xFFFF, 0x01);
+        cpu.mmu.write_byte(0xFF0F, 0x01);
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.cycles), (0x0101, 4));
+        assert_eq!(cpu.mmu.read_byte(0xFF0F), 0x01);
+    }
+
+    #[test]
+    fn halt_exits_on_pending_interrupt_without_ime() {
+        let mut cpu = setup(&[0x76, 0x3C]); // HALT; INC A
+        cpu.ime = false;
+        cpu.mmu.write_byte(0xFFFF, 0x04);
+        cpu.step();
+        cpu.step();
+        assert!(cpu.halted);
+        assert_eq!((cpu.regs.pc, cpu.cycles), (0x0101, 8));
+
+        cpu.mmu.write_byte(0xFF0F, 0x04);
+        let a = cpu.regs.a;
+        cpu.step();
+        assert!(!cpu.halted);
+        assert_eq!(cpu.regs.a, a.wrapping_add(1)); // ディスパッチせずに次の命令へ
+    }
+
+    #[test]
+    fn ldh_a_n_reads_high_page() {
+        let mut cpu = setup(&[0xF0, 0x80, 0xF0, 0x00]); // LDH A, (0x80); LDH A, (0x00)
+        cpu.mmu.write_byte(0xFF80, 0x5A);
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.pc, cpu.cycles), (0x5A, 0x0102, 12));
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0xFF); // ジョイパッド (未選択, 未入力)
+    }
+
+    #[test]
+    fn ld_a_nn_reads_absolute_address() {
+        let mut cpu = setup(&[0xFA, 0x34, 0xC2]); // LD A, (0xC234)
+        cpu.mmu.write_byte(0xC234, 0xA5);
+        cpu.step();
+        assert_eq!((cpu.regs.a, cpu.regs.pc, cpu.cycles), (0xA5, 0x0103, 16));
+    }
+
+    #[test]
+    fn ldh_c_a_writes_high_page() {
+        let mut cpu = setup(&[0xE2]); // LDH [C], A
+        cpu.regs.a = 0x42;
+        cpu.regs.c = 0x85;
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xFF85), 0x42);
+    }
+
+    #[test]
+    fn ldh_c_a_writes_io_register() {
+        let mut cpu = setup(&[0xE2]); // LDH [C], A
+        cpu.regs.a = 0x1F;
+        cpu.regs.c = 0x0F; // IF
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xFF0F), 0x1F);
+    }
+
+    #[test]
+    fn ldh_c_a_writes_scy() {
+        let mut cpu = setup(&[0xE2]); // LDH [C], A
+        cpu.regs.a = 0x42;
+        cpu.regs.c = 0x42; // SCY
+        cpu.step();
+        assert_eq!(cpu.mmu.read_byte(0xFF42), 0x42);
+        assert_eq!(cpu.mmu.read_io(0x42), 0x42);
+    }
+
+    #[test]
+    fn ldh_a_c_reads_high_page() {
+        let mut cpu = setup(&[0xF2]); // LDH A, [C]
+        cpu.mmu.write_byte(0xFF80, 0x99);
+        cpu.regs.c = 0x80;
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x99);
+    }
+
+    #[test]
+    fn ldh_a_c_reads_joypad() {
+        let mut cpu = setup(&[0xF2]); // LDH A, [C]
+        cpu.mmu.write_byte(0xFF00, 0x20); // 方向キーを選択
+        cpu.mmu.joypad_mut().poll_hardware_state(JoypadState {
+            right: true,
+            ..JoypadState::default()
+        });
+        cpu.regs.c = 0x00; // P1
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0xEE); // bit0 (右) だけ 0
+    }
+
+    #[test]
+    fn ld_a_hl_dec_wraps_to_ffff() {
+        let mut cpu = setup(&[0x3A]); // LD A, [HL-]
+        cpu.regs.a = 0x12;
+        cpu.regs.h = 0x00;
+        cpu.regs.l = 0x00;
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x00); // ROM[0x0000]
+        assert_eq!((cpu.regs.h, cpu.regs.l), (0xFF, 0xFF));
+    }
+
+    #[test]
+    fn daa_after_add() {
+        let mut cpu = setup(&[0x27]); // DAA
+        cpu.regs.a = 0x7D; // 0x45 + 0x38
+        cpu.regs.f = 0x00;
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x83);
+        assert_eq!(cpu.regs.f, 0x00);
+    }
+
+    #[test]
+    fn daa_after_sub_uses_flags_only() {
+        let mut cpu = setup(&[0x90, 0x27]); // SUB A, B; DAA
+        cpu.regs.a = 0x42;
+        cpu.regs.b = 0x15;
+        cpu.step(); // 0x2D, N, H
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x27);
+        assert_eq!(cpu.regs.f, 0x40);
+
+        // H が立っていなければ下位桁が 0x0A 以上でも補正しない
+        let mut cpu = setup(&[0x27]);
+        cpu.regs.a = 0x0F;
+        cpu.regs.f = 0x40;
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x0F);
+    }
+
+    #[test]
+    fn daa_after_sub_with_borrow() {
+        let mut cpu = setup(&[0x90, 0x27]); // SUB A, B; DAA
+        cpu.regs.a = 0x10;
+        cpu.regs.b = 0x20;
+        cpu.step(); // 0xF0, N, C
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0x90);
+        assert_eq!(cpu.regs.f, 0x50);
+    }
+
+    #[test]
+    fn stop_skips_second_byte() {
+        let mut cpu = setup(&[0x10, 0x00, 0x3C]); // STOP; INC A
+        cpu.step();
+        assert_eq!(cpu.stopped_mode, Some(StopMode::WaitJoypad));
+        assert_eq!(cpu.regs.pc, 0x0102);
+    }
+
+    #[test]
+    fn stop_switches_speed_when_key1_armed() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0100] = 0x10; // STOP
+        rom[0x0143] = 0x80; // CGB
+        let mut cpu = CPU::new(MMU::new(rom));
+        cpu.mmu.write_byte(0xFF4D, 0x01);
+        cpu.step();
+        assert_eq!(cpu.stopped_mode, Some(StopMode::SpeedSwitch));
+        assert!(cpu.mmu.double_speed());
+        assert_eq!(cpu.mmu.read_byte(0xFF4D), 0xFE);
+    }
+
+    #[test]
+    fn push_wraps_sp_at_zero() {
+        let mut cpu = setup(&[0xC5]); // PUSH BC
+        cpu.regs.sp = 0x0000;
+        cpu.regs.b = 0x12;
+        cpu.regs.c = 0x34;
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0xFFFE);
+        assert_eq!(cpu.mmu.read_byte(0xFFFF), 0x12);
+        assert_eq!(cpu.mmu.read_byte(0xFFFE), 0x34);
+    }
+
+    #[test]
+    fn pop_wraps_sp_past_ffff() {
+        let mut cpu = setup(&[0xD1]); // POP DE
+        cpu.mmu.write_byte(0xFFFE, 0x34);
+        cpu.mmu.write_byte(0xFFFF, 0x12);
+        cpu.regs.sp = 0xFFFE;
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0x0000);
+        assert_eq!((cpu.regs.d, cpu.regs.e), (0x12, 0x34));
+    }
+
+    #[test]
+    fn push_pop_af_masks_low_flag_bits() {
+        let mut cpu = setup(&[0xC5, 0xF1]); // PUSH BC; POP AF
+        cpu.regs.b = 0xAB;
+        cpu.regs.c = 0xFF;
+        cpu.step();
+        cpu.step();
+        assert_eq!(cpu.regs.a, 0xAB);
+        assert_eq!(cpu.regs.f, 0xF0);
+        assert_eq!(cpu.regs.sp, 0xFFFE);
+    }
+
+    #[test]
+    fn ld_hl_sp_e8_writes_both_bytes() {
+        let mut cpu = setup(&[0xF8, 0xFF]); // LD HL, SP-1
+        cpu.regs.sp = 0x0012;
+        cpu.regs.h = 0xAA;
+        cpu.step();
+        assert_eq!((cpu.regs.h, cpu.regs.l), (0x00, 0x11));
+        assert_eq!(cpu.regs.sp, 0x0012);
+        assert_eq!(cpu.regs.f, 0x30);
+    }
+
+    #[test]
+    fn add_sp_e8_negative_offset_at_zero() {
+        let mut cpu = setup(&[0xE8, 0xFF]); // ADD SP, -1
+        cpu.regs.sp = 0x0000;
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0xFFFF);
+        assert_eq!(cpu.regs.f, 0x00); // 0x00 + 0xFF は bit3, bit7 から桁上がりしない
+    }
+
+    #[test]
+    fn add_sp_e8_negative_offset_sets_h_and_c() {
+        let mut cpu = setup(&[0xE8, 0xFF]); // ADD SP, -1
+        cpu.regs.sp = 0x0001;
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0x0000);
+        assert_eq!(cpu.regs.f, 0x30); // Z は常にクリア
+    }
+
+    #[test]
+    fn add_sp_e8_positive_offset() {
+        let mut cpu = setup(&[0xE8, 0x08]); // ADD SP, 8
+        cpu.regs.sp = 0xFFF8;
+        cpu.step();
+        assert_eq!(cpu.regs.sp, 0x0000);
+        assert_eq!(cpu.regs.f, 0x30);
+    }
+
+    #[test]
+    fn call_and_ret_cycles() {
+        let mut program = vec![0x00; 0x11];
+        program[0..3].copy_from_slice(&[0xCD, 0x10, 0x01]); // CALL 0x0110
+        let mut cpu = setup(&program);
+        cpu.regs.sp = 0xD000;
+        cpu.fetch();
+        assert_eq!(cpu.call_nn(), 24);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0110, 0xCFFE));
+        assert_eq!(cpu.mmu.read_byte(0xCFFE), 0x03); // 戻り先 0x0103
+        assert_eq!(cpu.mmu.read_byte(0xCFFF), 0x01);
+
+        assert_eq!(cpu.ret(), 16);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0103, 0xD000));
+    }
+
+    #[test]
+    fn call_cond_cycles_and_stack() {
+        // CALL NZ, 0x1234 (呼ばない); CALL Z, 0x5678 (呼ぶ)
+        let mut cpu = setup(&[0xC4, 0x34, 0x12, 0xCC, 0x78, 0x56]);
+        cpu.regs.sp = 0xD000;
+        cpu.regs.f = 0x80; // Z
+        cpu.fetch();
+        assert_eq!(cpu.call_cond(ConditionType::NZ), 12);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0103, 0xD000));
+        cpu.fetch();
+        assert_eq!(cpu.call_cond(ConditionType::Z), 24);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x5678, 0xCFFE));
+        assert_eq!(cpu.mmu.read_byte(0xCFFE), 0x06);
+    }
+
+    #[test]
+    fn ret_cond_and_reti_cycles() {
+        let mut cpu = setup(&[]);
+        cpu.regs.sp = 0xCFFE;
+        cpu.mmu.write_byte(0xCFFE, 0x34);
+        cpu.mmu.write_byte(0xCFFF, 0x12);
+        cpu.regs.f = 0x10; // C
+        assert_eq!(cpu.ret_cond(ConditionType::NC), 8);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0100, 0xCFFE));
+        assert_eq!(cpu.ret_cond(ConditionType::C), 20);
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x1234, 0xD000));
+
+        cpu.regs.sp = 0xCFFE;
+        cpu.ime = false;
+        assert_eq!(cpu.reti(), 16);
+        assert!(cpu.ime);
+        assert_eq!(cpu.regs.pc, 0x1234);
+    }
+
+    #[test]
+    fn ret_cond_cycles_for_every_condition() {
+        // RET cc: 戻ったら 16 + 条件判定4 = 20, 戻らなければ8
+        for (condition, f, cycles) in [
+            (ConditionType::NZ, 0x00, 20),
+            (ConditionType::NZ, 0x80, 8),
+            (ConditionType::Z, 0x80, 20),
+            (ConditionType::Z, 0x00, 8),
+            (ConditionType::NC, 0x00, 20),
+            (ConditionType::NC, 0x10, 8),
+            (ConditionType::C, 0x10, 20),
+            (ConditionType::C, 0x00, 8),
+        ] {
+            let mut cpu = setup(&[]);
+            cpu.regs.sp = 0xCFFE;
+            cpu.regs.f = f;
+            cpu.mmu.write_byte(0xCFFE, 0x00);
+            cpu.mmu.write_byte(0xCFFF, 0x02);
+            assert_eq!(cpu.ret_cond(condition), cycles, "F={:02X}", f);
+            let pc = if cycles == 20 { 0x0200 } else { 0x0100 };
+            assert_eq!(cpu.regs.pc, pc);
+        }
+    }
+
+    #[test]
+    fn call_nc_c_through_execute() {
+        // (opcode, F, 行き先)
+        for (op, f, pc) in [(0xD4, 0x00, 0x4000), (0xD4, 0x10, 0x0103), (0xDC, 0x10, 0x4000), (0xDC, 0x00, 0x0103)] {
+            let mut cpu = setup(&[op, 0x00, 0x40]);
+            cpu.regs.sp = 0xD000;
+            cpu.regs.f = f;
+            cpu.step();
+            assert_eq!(cpu.regs.pc, pc, "op {:02X} F={:02X}", op, f);
+            assert_eq!(cpu.cycles, if pc == 0x4000 { 24 } else { 12 });
+        }
+    }
+
+    #[test]
+    fn call_then_ret_through_execute() {
+        let mut program = vec![0x00; 0x11];
+        program[0..3].copy_from_slice(&[0xCD, 0x10, 0x01]); // CALL 0x0110
+        program[0x10] = 0xC9; // RET
+        let mut cpu = setup(&program);
+        cpu.regs.sp = 0xD000;
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0110, 0xCFFE));
+        assert_eq!(cpu.cycles, 24);
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.regs.sp), (0x0103, 0xD000));
+        assert_eq!(cpu.cycles, 24 + 16);
+    }
+
+    #[test]
+    fn ret_cond_and_reti_through_execute() {
+        // RET NZ; RET Z; RETI (RET Z の戻り先)
+        let mut cpu = setup(&[0xC0, 0xC8, 0xD9]);
+        cpu.regs.sp = 0xCFFC;
+        cpu.mmu.write_word(0xCFFC, 0x0102);
+        cpu.mmu.write_word(0xCFFE, 0x0150);
+        cpu.regs.f = 0x80; // Z
+        cpu.ime = false;
+        cpu.step(); // 戻らないので SP はそのまま
+        assert_eq!((cpu.regs.pc, cpu.regs.sp, cpu.cycles), (0x0101, 0xCFFC, 8));
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.regs.sp, cpu.cycles), (0x0102, 0xCFFE, 28));
+        cpu.step();
+        assert_eq!((cpu.regs.pc, cpu.regs.sp, cpu.cycles), (0x0150, 0xD000, 44));
+        assert!(cpu.ime);
+    }
+
+    #[test]
+    fn add_sp_e8_at_ffff() {
+        // (e8, 結果, F)
+        for (e8, result, f) in [(0x01, 0x0000, 0x30), (0xFF, 0xFFFE, 0x30), (0x00, 0xFFFF, 0x00)] {
+            let mut cpu = setup(&[0xE8, e8]); // ADD SP, e8
+            cpu.regs.sp = 0xFFFF;
+            cpu.regs.f = 0xC0; // Z, N は必ずクリア
+            cpu.step();
+            assert_eq!((cpu.regs.sp, cpu.regs.f), (result, f), "e8={:02X}", e8);
+        }
+    }
+
+    #[test]
+    fn ld_hl_sp_e8_edges_keep_sp() {
+        // (SP, e8, HL, F)
+        for (sp, e8, hl, f) in [
+            (0xFFFF, 0x01, 0x0000, 0x30),
+            (0x0000, 0xFF, 0xFFFF, 0x00), // 下位バイト 0x00 + 0xFF はキャリーなし
+            (0x0000, 0x01, 0x0001, 0x00),
+        ] {
+            let mut cpu = setup(&[0xF8, e8]); // LD HL, SP+e8
+            cpu.regs.sp = sp;
+            cpu.step();
+            let actual = cpu.regs.get_hl();
+            assert_eq!((actual, cpu.regs.f), (hl, f), "SP={:04X} e8={:02X}", sp, e8);
+            assert_eq!(cpu.regs.sp, sp);
+            assert_eq!(cpu.cycles, 12);
+        }
+    }
+}
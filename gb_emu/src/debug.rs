@@ -0,0 +1,92 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::Path;
+
+// ROMアドレスとシンボル名の対応表 (RGBDS などの .sym ファイルから読み込む)
+// バンク切り替えで同じアドレスに別のラベルが付くので (バンク, アドレス) で引く
+pub struct DebugMap {
+    pub symbols: HashMap<(u16, u16), String>,
+}
+
+impl DebugMap {
+    pub fn new() -> Self {
+        Self {
+            symbols: HashMap::new(),
+        }
+    }
+
+    pub fn load_from_sym_file(path: &Path) -> io::Result<Self> {
+        Ok(Self::parse(&fs::read_to_string(path)?))
+    }
+
+    // 1行ごとに "BANK:ADDR LABEL" (16進数)。 ';' 以降はコメント
+    // 形式に合わない行は読み飛ばす
+    pub fn parse(text: &str) -> Self {
+        let mut map = Self::new();
+        for line in text.lines() {
+            let line = line.split(';').next().unwrap_or("").trim();
+            let Some((location, label)) = line.split_once(char::is_whitespace) else {
+                continue;
+            };
+            let Some((bank, addr)) = location.split_once(':') else {
+                continue;
+            };
+            if let (Ok(bank), Ok(addr)) =
+                (u16::from_str_radix(bank, 16), u16::from_str_radix(addr, 16))
+            {
+                map.symbols.insert((bank, addr), label.trim().to_string());
+            }
+        }
+        map
+    }
+
+    pub fn label(&self, bank: u16, addr: u16) -> Option<&str> {
+        self.symbols.get(&(bank, addr)).map(|s| s.as_str())
+    }
+}
+
+impl Default for DebugMap {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_sym_lines() {
+        let map = DebugMap::parse(
+            "; File generated by rgblink\n\
+             00:0150 Start\n\
+             00:0234 GAME_INIT ; comment\n\
+             01:4000 Bank1Entry\n\
+             02:4000 Bank2Entry\n\
+             \n\
+             broken line\n",
+        );
+        assert_eq!(map.symbols.len(), 4);
+        assert_eq!(map.label(0x00, 0x0150), Some("Start"));
+        assert_eq!(map.label(0x00, 0x0234), Some("GAME_INIT"));
+        assert_eq!(map.label(0x00, 0x0000), None);
+        // 同じアドレスでもバンクごとに別のラベル
+        assert_eq!(map.label(0x01, 0x4000), Some("Bank1Entry"));
+        assert_eq!(map.label(0x02, 0x4000), Some("Bank2Entry"));
+        assert_eq!(map.label(0x03, 0x4000), None);
+    }
+
+    #[test]
+    fn load_from_sym_file_reads_symbols() {
+        let path = std::env::temp_dir().join(format!("gb_emu_test_{}.sym", std::process::id()));
+        fs::write(&path, "00:0150 Start\n1F:7FFF LastByte\n").unwrap();
+        let map = DebugMap::load_from_sym_file(&path);
+        fs::remove_file(&path).unwrap();
+        let map = map.unwrap();
+        assert_eq!(map.label(0x00, 0x0150), Some("Start"));
+        assert_eq!(map.label(0x1F, 0x7FFF), Some("LastByte"));
+
+        assert!(DebugMap::load_from_sym_file(&path).is_err()); // 削除済み
+    }
+}
@@ -0,0 +1,125 @@
+// IE (0xFFFF) / IF (0xFF0F) の割り込みビット
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct InterruptFlags(pub u8);
+
+impl InterruptFlags {
+    pub const VBLANK: InterruptFlags = InterruptFlags(0x01); // bit0
+    pub const LCD_STAT: InterruptFlags = InterruptFlags(0x02); // bit1
+    pub const TIMER: InterruptFlags = InterruptFlags(0x04); // bit2
+    pub const SERIAL: InterruptFlags = InterruptFlags(0x08); // bit3
+    pub const JOYPAD: InterruptFlags = InterruptFlags(0x10); // bit4
+
+    pub fn vblank(&self) -> bool {
+        self.get(Self::VBLANK)
+    }
+
+    pub fn lcd_stat(&self) -> bool {
+        self.get(Self::LCD_STAT)
+    }
+
+    pub fn timer(&self) -> bool {
+        self.get(Self::TIMER)
+    }
+
+    pub fn serial(&self) -> bool {
+        self.get(Self::SERIAL)
+    }
+
+    pub fn joypad(&self) -> bool {
+        self.get(Self::JOYPAD)
+    }
+
+    pub fn set_vblank(&mut self, v: bool) {
+        self.set(Self::VBLANK, v);
+    }
+
+    pub fn set_lcd_stat(&mut self, v: bool) {
+        self.set(Self::LCD_STAT, v);
+    }
+
+    pub fn set_timer(&mut self, v: bool) {
+        self.set(Self::TIMER, v);
+    }
+
+    pub fn set_serial(&mut self, v: bool) {
+        self.set(Self::SERIAL, v);
+    }
+
+    pub fn set_joypad(&mut self, v: bool) {
+        self.set(Self::JOYPAD, v);
+    }
+
+    fn get(&self, mask: InterruptFlags) -> bool {
+        self.0 & mask.0 != 0
+    }
+
+    fn set(&mut self, mask: InterruptFlags, v: bool) {
+        if v {
+            self.0 |= mask.0;
+        } else {
+            self.0 &= !mask.0;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    type Getter = fn(&InterruptFlags) -> bool;
+    type Setter = fn(&mut InterruptFlags, bool);
+
+    const ACCESSORS: [(InterruptFlags, Getter, Setter); 5] = [
+        (
+            InterruptFlags::VBLANK,
+            InterruptFlags::vblank,
+            InterruptFlags::set_vblank,
+        ),
+        (
+            InterruptFlags::LCD_STAT,
+            InterruptFlags::lcd_stat,
+            InterruptFlags::set_lcd_stat,
+        ),
+        (
+            InterruptFlags::TIMER,
+            InterruptFlags::timer,
+            InterruptFlags::set_timer,
+        ),
+        (
+            InterruptFlags::SERIAL,
+            InterruptFlags::serial,
+            InterruptFlags::set_serial,
+        ),
+        (
+            InterruptFlags::JOYPAD,
+            InterruptFlags::joypad,
+            InterruptFlags::set_joypad,
+        ),
+    ];
+
+    #[test]
+    fn setters_toggle_only_their_bit() {
+        for (mask, get, set) in ACCESSORS {
+            let mut flags = InterruptFlags(0);
+            set(&mut flags, true);
+            assert_eq!(flags, mask);
+            assert!(get(&flags));
+
+            let mut flags = InterruptFlags(0xFF);
+            set(&mut flags, false);
+            assert_eq!(flags.0, !mask.0);
+            assert!(!get(&flags));
+        }
+    }
+
+    #[test]
+    fn getters_match_bit_constants() {
+        for (i, (mask, _, _)) in ACCESSORS.into_iter().enumerate() {
+            assert_eq!(mask.0, 1 << i);
+            for (other, get, _) in ACCESSORS {
+                assert_eq!(get(&mask), other == mask);
+            }
+        }
+    }
+}
@@ -0,0 +1,112 @@
+// フロントエンドから渡すボタンの押下状態 (true = 押されている)
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct JoypadState {
+    pub up: bool,
+    pub down: bool,
+    pub left: bool,
+    pub right: bool,
+    pub a: bool,
+    pub b: bool,
+    pub start: bool,
+    pub select: bool,
+}
+
+// ジョイパッドレジスタ P1 (0xFF00)
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Joypad {
+    selection: u8, // bit5: P15 (ボタン), bit4: P14 (方向キー), 0 = 選択
+    state: JoypadState,
+}
+
+impl Joypad {
+    pub fn new() -> Self {
+        Self {
+            selection: 0x30,
+            state: JoypadState::default(),
+        }
+    }
+
+    // フレームごとに実機の入力状態を反映する
+    pub fn poll_hardware_state(&mut self, hardware_state: JoypadState) {
+        self.state = hardware_state;
+    }
+
+    // ゲームからの書き込みは選択ビットのみ有効
+    pub fn write(&mut self, value: u8) {
+        self.selection = value & 0x30;
+    }
+
+    // 読み出し時に選択ビットと入力状態から値を計算する
+    pub fn read(&self) -> u8 {
+        0xC0 | self.selection | self.button_bits()
+    }
+
+    // 下位4bit (0 = 押されている)
+    fn button_bits(&self) -> u8 {
+        let mut bits = 0x0F;
+        if self.selection & 0x10 == 0 {
+            // 方向キー
+            bits &= !Self::pressed_bits(
+                self.state.right,
+                self.state.left,
+                self.state.up,
+                self.state.down,
+            );
+        }
+        if self.selection & 0x20 == 0 {
+            // ボタン
+            bits &= !Self::pressed_bits(
+                self.state.a,
+                self.state.b,
+                self.state.select,
+                self.state.start,
+            );
+        }
+        bits
+    }
+
+    fn pressed_bits(bit0: bool, bit1: bool, bit2: bool, bit3: bool) -> u8 {
+        (bit0 as u8) | ((bit1 as u8) << 1) | ((bit2 as u8) << 2) | ((bit3 as u8) << 3)
+    }
+}
+
+impl Default for Joypad {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn press(state: JoypadState, selection: u8) -> u8 {
+        let mut joypad = Joypad::new();
+        joypad.poll_hardware_state(state);
+        joypad.write(selection);
+        joypad.read()
+    }
+
+    #[test]
+    fn a_button_shows_on_bit0_when_p15_selected() {
+        let a = JoypadState {
+            a: true,
+            ..Default::default()
+        };
+        assert_eq!(press(a, 0x10), 0xDE); // P15選択: bit0 = 0 (押されている)
+        assert_eq!(press(a, 0x20), 0xEF); // P14選択: 方向キーのみ
+        assert_eq!(press(a, 0x30), 0xFF); // 非選択: 読めない
+    }
+
+    #[test]
+    fn both_groups_selected_are_anded() {
+        let state = JoypadState {
+            a: true,
+            down: true,
+            ..Default::default()
+        };
+        assert_eq!(press(state, 0x00), 0xC6); // bit0 (A), bit3 (下)
+        assert_eq!(press(state, 0xFF), 0xFF); // 選択ビット以外の書き込みは無視
+    }
+}
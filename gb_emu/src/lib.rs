@@ -0,0 +1,13 @@
+pub mod apu;
+pub mod cartridge;
+pub mod coverage;
+pub mod cpu;
+pub mod debug;
+pub mod interrupt;
+pub mod joypad;
+pub mod mmu;
+pub mod model;
+pub mod palette;
+pub mod ppu;
+pub mod printer;
+pub mod serial;
@@ -1,8 +1,6 @@
-mod cpu;
-mod mmu;
-
-use cpu::CPU;
-use mmu::MMU;
+use gb_emu::cpu::CPU;
+use gb_emu::mmu::MMU;
+use gb_emu::ppu::PPU;
 
 fn main() {
     // Initialize Rom DATA
@@ -14,9 +12,11 @@ fn main() {
     // STOP
     rom_data[0x0101] = 0x10;
 
-    // Make MMU & CPU
+    // Make MMU & CPU & PPU
     let mmu = MMU::new(rom_data);
     let mut cpu = CPU::new(mmu);
+    let mut ppu = PPU::new();
+    cpu.install_panic_hook(); // パニック時にCPU状態を表示
 
     cpu.regs.a = 0x00;
     cpu.regs.f = 0x10;
@@ -26,7 +26,10 @@ fn main() {
     // Exec ROM
     let n_op = 2; // 命令の数
     for _ in 0..n_op {
+        let before = cpu.cycles();
         cpu.step();
+        let cycles = (cpu.cycles() - before) as u8;
+        ppu.step(&mut cpu.mmu, cycles); // CPUが進んだ分だけPPUを進める
         println!(
             "A: 0x{:02X}, B: 0x{:02X}, C: 0x{:02X}, D: 0x{:02X}, E: 0x{:02X}, H: 0x{:02X}, L: 0x{:02X}, SP: 0x{:04X}, PC: 0x{:04X}",
             cpu.regs.a,
@@ -44,6 +47,7 @@ fn main() {
         println!("0xFF81: 0x{:04X}", cpu.mmu.read_byte(0xFF81));
         println!("0xFF82: 0x{:04X}", cpu.mmu.read_byte(0xFF82));
         println!("0xFFFF: 0x{:02X}", cpu.mmu.read_byte(0xFFFF));
+        println!("PPU: {:?}, LY: {}", ppu.mode(), ppu.ly());
         println!("---");
     }
 }
@@ -1,50 +1,1098 @@
+use crate::apu::APU;
+use crate::cartridge::{Mbc, Rom, external_ram_size};
+use crate::interrupt::InterruptFlags;
+use crate::joypad::Joypad;
+use crate::model::GameBoyModel;
+use crate::palette::GbcPaletteRam;
+use crate::serial::SerialLink;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum AccessKind {
+    Read,
+    Write,
+}
+
+// 1回分のメモリアクセス記録
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AccessEntry {
+    pub cycle: u64, // アクセス時のサイクル (set_cycle で設定された値)
+    pub addr: u16,
+    pub value: u8,
+    pub kind: AccessKind,
+}
+
+// read_byte / write_byte のアクセス履歴
+// max_entries に達した後のアクセスは記録しない (drain_log で空にする)
+pub struct MemoryAccessLog {
+    entries: Vec<AccessEntry>,
+    max_entries: usize,
+}
+
+impl MemoryAccessLog {
+    fn record(&mut self, entry: AccessEntry) {
+        if self.entries.len() < self.max_entries {
+            self.entries.push(entry);
+        }
+    }
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct MMU {
-    rom: Vec<u8>,       // ROM Data
-    wram: [u8; 0x2000], // Work RAM (8KB)
-    hram: [u8; 0x7F],   // High RAM (127B)
-    ie: u8,             // Interrupt Register (0xFFFF)
-    interrupt_flag: u8, // Interrupt Flag (0xFF0F)
+    model: GameBoyModel, // DMG / CGB
+    #[cfg_attr(feature = "serde", serde(skip))] // ROMはセーブステートに含めない
+    rom: Rom, // ROM Data
+    mbc: Mbc,            // バンクコントローラ
+    #[cfg_attr(feature = "serde", serde(with = "banks_serde"))]
+    vram: Box<[[u8; 0x2000]; 2]>, // Video RAM (8KB x 2, DMGはバンク0のみ)
+    vbk: u8,             // VRAM Bank Select (0xFF4F, CGBのみ)
+    eram: Vec<u8>,       // External RAM (サイズはヘッダ 0x0149)
+    #[cfg_attr(feature = "serde", serde(with = "banks_serde"))]
+    wram: Box<[[u8; 0x1000]; 8]>, // Work RAM (4KB x 8, DMGはバンク0-1のみ)
+    svbk: u8,            // WRAM Bank Select (0xFF70, CGBのみ)
+    key1: u8,            // Speed Switch (0xFF4D, CGBのみ)
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    hram: [u8; 0x7F], // High RAM (127B)
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    oam: [u8; 0xA0], // Object Attribute Memory (4B x 40)
+    lcd: [u8; 0x0C],     // LCD Registers (0xFF40 - 0xFF4B)
+    oam_dma_source: u16, // OAM DMA の転送元 (0xFF46 の値 << 8)
+    oam_dma_remaining: u8, // OAM DMA の残りMサイクル数 (0 = 停止中)
+    strict_dma_timing: bool, // trueの間、DMA中のHRAM以外へのアクセスを遮断
+    apu: APU,            // Sound Registers
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    io: [u8; 0x80], // I/O Registers (個別に処理しないもの)
+    joypad: Joypad,      // Joypad (0xFF00)
+    sb: u8,              // Serial Data (0xFF01)
+    sc: u8,              // Serial Control (0xFF02)
+    #[cfg_attr(feature = "serde", serde(skip))]
+    serial_link: Option<Box<dyn SerialLink>>, // 通信ケーブルの先の機器 (未接続なら None)
+    bg_palette: GbcPaletteRam, // BG Palette RAM (0xFF68, 0xFF69, CGBのみ)
+    obj_palette: GbcPaletteRam, // OBJ Palette RAM (0xFF6A, 0xFF6B, CGBのみ)
+    ie: InterruptFlags,  // Interrupt Register (0xFFFF)
+    interrupt_flag: InterruptFlags, // Interrupt Flag (0xFF0F)
+    cycle: u64,          // アクセス履歴のタイムスタンプ
+    #[cfg_attr(feature = "serde", serde(skip))]
+    access_log: Option<MemoryAccessLog>, // Memory Access Log (有効時のみ)
 }
 
 impl MMU {
     // init MMU
     pub fn new(rom_data: Vec<u8>) -> Self {
+        let model = GameBoyModel::from_rom(&rom_data);
         Self {
-            rom: rom_data,
-            wram: [0; 0x2000],
+            model,
+            mbc: Mbc::from_rom(&rom_data),
+            eram: vec![0; external_ram_size(rom_data.get(0x0149).copied().unwrap_or(0))],
+            rom: Rom::new(rom_data),
+            vram: Box::new([[0; 0x2000]; 2]),
+            vbk: 0,
+            wram: Box::new([[0; 0x1000]; 8]),
+            svbk: 0,
+            key1: 0,
             hram: [0; 0x7F],
-            ie: 0,
-            interrupt_flag: 0,
+            oam: [0; 0xA0],
+            lcd: [0; 0x0C],
+            oam_dma_source: 0,
+            oam_dma_remaining: 0,
+            strict_dma_timing: false,
+            apu: APU::new(model.is_cgb()),
+            io: [0; 0x80],
+            joypad: Joypad::new(),
+            sb: 0,
+            sc: 0,
+            serial_link: None,
+            bg_palette: GbcPaletteRam::new(),
+            obj_palette: GbcPaletteRam::new(),
+            ie: InterruptFlags(0),
+            interrupt_flag: InterruptFlags(0),
+            cycle: 0,
+            access_log: None,
+        }
+    }
+
+    // セーブステートの読み込み後にROMを差し戻す
+    pub fn reload_rom(&mut self, rom_data: Vec<u8>) {
+        self.rom = Rom::new(rom_data);
+    }
+
+    // アクセス履歴の記録を開始 (最大 max_entries 件)
+    pub fn enable_access_log(&mut self, max_entries: usize) {
+        self.access_log = Some(MemoryAccessLog {
+            entries: Vec::with_capacity(max_entries),
+            max_entries,
+        });
+    }
+
+    pub fn disable_access_log(&mut self) {
+        self.access_log = None;
+    }
+
+    // 記録済みのアクセス履歴を取り出す
+    pub fn drain_log(&mut self) -> Vec<AccessEntry> {
+        match self.access_log.as_mut() {
+            Some(log) => log.entries.drain(..).collect(),
+            None => Vec::new(),
+        }
+    }
+
+    pub fn model(&self) -> GameBoyModel {
+        self.model
+    }
+
+    pub fn joypad_mut(&mut self) -> &mut Joypad {
+        &mut self.joypad
+    }
+
+    pub fn interrupt_enable(&self) -> InterruptFlags {
+        self.ie
+    }
+
+    pub fn interrupt_flag(&self) -> InterruptFlags {
+        self.interrupt_flag
+    }
+
+    // CGB専用レジスタ (KEY1, VBK, HDMA1-5, BCPS/BCPD/OCPS/OCPD, SVBK)
+    fn is_cgb_register(addr: u16) -> bool {
+        matches!(
+            addr,
+            0xFF4D | 0xFF4F | 0xFF51..=0xFF56 | 0xFF68..=0xFF6B | 0xFF70
+        )
+    }
+
+    // KEY1 bit0: 次の STOP で速度を切り替える
+    pub fn speed_switch_armed(&self) -> bool {
+        self.model.is_cgb() && self.key1 & 0x01 != 0
+    }
+
+    // 速度を切り替え (bit7 反転, bit0 クリア)
+    pub fn switch_speed(&mut self) {
+        self.key1 = (self.key1 ^ 0x80) & 0x80;
+    }
+
+    pub fn double_speed(&self) -> bool {
+        self.key1 & 0x80 != 0
+    }
+
+    // 0xD000 - 0xDFFF に割り当てるWRAMバンク (SVBK=0 はバンク1)
+    fn wram_bank_number(&self) -> usize {
+        match self.svbk & 0x07 {
+            0 => 1,
+            bank => bank as usize,
+        }
+    }
+
+    // WRAMバンクをまとめて参照する (DMA などの一括読み出し用)
+    pub fn wram_bank(&self, bank: usize) -> &[u8; 0x1000] {
+        &self.wram[bank]
+    }
+
+    // 0xD000 - 0xDFFF に割り当て中のバンク
+    pub fn current_wram_bank(&self) -> &[u8; 0x1000] {
+        &self.wram[self.wram_bank_number()]
+    }
+
+    // WRAM先頭からのオフセットを (バンク, バンク内オフセット) に変換
+    // 0xC000 - 0xCFFF は常にバンク0
+    fn wram_index(&self, offset: u16) -> (usize, usize) {
+        if offset < 0x1000 {
+            (0, offset as usize)
+        } else {
+            (self.wram_bank_number(), (offset - 0x1000) as usize)
+        }
+    }
+
+    // 以降のアクセス履歴に付けるサイクル数を設定
+    pub fn set_cycle(&mut self, cycle: u64) {
+        self.cycle = cycle;
+    }
+
+    fn log_access(&mut self, addr: u16, value: u8, kind: AccessKind) {
+        if let Some(log) = self.access_log.as_mut() {
+            log.record(AccessEntry {
+                cycle: self.cycle,
+                addr,
+                value,
+                kind,
+            });
+        }
+    }
+
+    // IF の該当bitを立てる (PPU などのハードウェアからの割り込み要求)
+    // Timer, PPU, Joypad, Serial などは必ずこれを使う
+    pub fn request_interrupt(&mut self, interrupt: InterruptFlags) {
+        self.interrupt_flag.0 |= interrupt.0;
+    }
+
+    // 割り込み要求の取り消し (IF のビットを下ろす)
+    pub fn clear_interrupt(&mut self, interrupt: InterruptFlags) {
+        self.interrupt_flag.0 &= !interrupt.0;
+    }
+
+    pub fn apu(&self) -> &APU {
+        &self.apu
+    }
+
+    pub fn apu_mut(&mut self) -> &mut APU {
+        &mut self.apu
+    }
+
+    // VRAMバンクをまとめて参照する (PPU のタイルデータ・属性の読み出し用)
+    pub fn vram_bank(&self, bank: usize) -> &[u8; 0x2000] {
+        &self.vram[bank]
+    }
+
+    // 0x8000 - 0x9FFF に割り当て中のバンク
+    pub fn current_vram_bank(&self) -> &[u8; 0x2000] {
+        &self.vram[self.vbk as usize]
+    }
+
+    // スプライト属性 (PPU のスプライト走査用)
+    pub fn oam(&self) -> &[u8; 0xA0] {
+        &self.oam
+    }
+
+    // 通信ケーブルに機器をつなぐ (プリンタなど)
+    pub fn connect_serial(&mut self, link: Box<dyn SerialLink>) {
+        self.serial_link = Some(link);
+    }
+
+    // SC bit7 (転送開始) と bit0 (内部クロック) が立ったら1バイトやり取りする
+    // 転送時間は待たずにすぐ終わらせ、シリアル割り込みを要求する
+    fn serial_transfer(&mut self) {
+        self.sb = match self.serial_link.as_mut() {
+            Some(link) => link.transfer(self.sb),
+            None => 0xFF, // 未接続なら 0xFF を受け取る
+        };
+        self.sc &= 0x7F;
+        self.request_interrupt(InterruptFlags::SERIAL);
+    }
+
+    // OAM DMA の転送中か (0xFF46 への書き込みから 160 Mサイクル)
+    pub fn in_oam_dma(&self) -> bool {
+        self.oam_dma_remaining > 0
+    }
+
+    // 有効にすると、DMA中の HRAM 以外への読み書きを警告して 0xFF / 無視にする
+    pub fn set_strict_dma_timing(&mut self, enabled: bool) {
+        self.strict_dma_timing = enabled;
+    }
+
+    fn start_oam_dma(&mut self, value: u8) {
+        self.oam_dma_source = (value as u16) << 8;
+        self.oam_dma_remaining = 0xA0;
+    }
+
+    // OAM DMA を m_cycles だけ進める (1 Mサイクルで1バイト転送)
+    pub fn step_dma(&mut self, m_cycles: u8) {
+        for _ in 0..m_cycles {
+            if !self.in_oam_dma() {
+                return;
+            }
+            let index = 0xA0 - self.oam_dma_remaining as u16;
+            self.oam[index as usize] = self.lookup(self.oam_dma_source + index).unwrap_or(0xFF);
+            self.oam_dma_remaining -= 1;
         }
     }
 
+    // DMA中に CPU から触れない領域か (HRAM のみアクセスできる)
+    fn blocked_by_dma(&self, addr: u16) -> bool {
+        self.strict_dma_timing && self.in_oam_dma() && !(0xFF80..=0xFFFE).contains(&addr)
+    }
+
     // read Memory
-    pub fn read_byte(&self, addr: u16) -> u8 {
-        match addr {
-            0x0000..=0x7FFF => self.rom[addr as usize], // ROM領域
-            0xC000..=0xDFFF => self.wram[(addr - 0xC000) as usize], // WRAM
-            0xE000..=0xFDFF => self.wram[(addr - 0xE000) as usize], // WRAM mirror
-            0xFF80..=0xFFFE => self.hram[(addr - 0xFF80) as usize], // HRAM
-            0xFFFF => self.ie,                          // 割り込みレジスタ
-            0xFF0F => self.interrupt_flag,              // 割り込みフラグ
-            _ => {
+    pub fn read_byte(&mut self, addr: u16) -> u8 {
+        if self.blocked_by_dma(addr) {
+            eprintln!("Warning: Read during OAM DMA: 0x{:04X}", addr);
+            self.log_access(addr, 0xFF, AccessKind::Read);
+            return 0xFF;
+        }
+        let value = match self.lookup(addr) {
+            Some(value) => value,
+            None => {
                 eprintln!("Wraning: Read from unmapped memory: 0x{:04X}", addr);
                 0xFF // 未定義領域は 0xFF を返す
             }
+        };
+        self.log_access(addr, value, AccessKind::Read);
+        value
+    }
+
+    // デバッガ用: 副作用 (警告出力・レジスタの読み出し時処理) なしで読む
+    pub fn peek(&self, addr: u16) -> u8 {
+        self.lookup(addr).unwrap_or(0xFF)
+    }
+
+    // 各領域の値をそのまま返す (未定義領域は None, peek もここを通る)
+    fn lookup(&self, addr: u16) -> Option<u8> {
+        if !self.model.is_cgb() && Self::is_cgb_register(addr) {
+            return Some(0xFF); // DMGではCGB専用レジスタは存在しない
+        }
+        match addr {
+            0x0000..=0x3FFF => Some(self.rom.read_bank_n(self.mbc.rom_bank_low(), addr)), // ROMバンク0
+            0x4000..=0x7FFF => {
+                // ROMバンク1-N
+                let bank = self.mbc.rom_bank_effective();
+                Some(self.rom.read_bank_n(bank, addr - 0x4000))
+            }
+            0x8000..=0x9FFF => Some(self.current_vram_bank()[(addr - 0x8000) as usize]), // VRAM
+            0xA000..=0xBFFF => Some(self.read_eram(addr)), // 外部RAM / RTC
+            0xC000..=0xDFFF => {
+                let (bank, offset) = self.wram_index(addr - 0xC000); // WRAM
+                Some(self.wram[bank][offset])
+            }
+            0xE000..=0xFDFF => {
+                let (bank, offset) = self.wram_index(addr - 0xE000); // WRAM mirror
+                Some(self.wram[bank][offset])
+            }
+            0xFE00..=0xFE9F => Some(self.oam[(addr - 0xFE00) as usize]), // OAM
+            0xFF00..=0xFF7F => Some(self.lookup_io(addr)),               // I/Oレジスタ
+            0xFF80..=0xFFFE => Some(self.hram[(addr - 0xFF80) as usize]), // HRAM
+            0xFFFF => Some(self.ie.0),                                   // 割り込みレジスタ
+            _ => None,
         }
     }
 
+    // I/Oレジスタ (0xFF00 - 0xFF7F) の値 (個別に処理しないものは io 配列)
+    fn lookup_io(&self, addr: u16) -> u8 {
+        match addr {
+            0xFF00 => self.joypad.read(),                          // ジョイパッド
+            0xFF01 => self.sb,                                     // シリアルデータ
+            0xFF02 => self.sc | 0x7E,                              // シリアル制御
+            0xFF4D => self.key1 | 0x7E,                            // KEY1
+            0xFF4F => self.vbk | 0xFE,                             // VRAMバンク選択
+            0xFF68 => self.bg_palette.read_spec(),                 // BCPS
+            0xFF69 => self.bg_palette.read_data(),                 // BCPD
+            0xFF6A => self.obj_palette.read_spec(),                // OCPS
+            0xFF6B => self.obj_palette.read_data(),                // OCPD
+            0xFF70 => self.svbk | 0xF8,                            // WRAMバンク選択
+            0xFF0F => self.interrupt_flag.0,                       // 割り込みフラグ
+            0xFF10..=0xFF14 => self.apu.read(addr),                // チャンネル1 (NR10 - NR14)
+            0xFF26 => self.apu.read(addr),                         // NR52 (サウンド電源)
+            0xFF30..=0xFF3F => self.apu.read(addr),                // 波形RAM
+            0xFF40..=0xFF4B => self.lcd[(addr - 0xFF40) as usize], // LCDレジスタ
+            _ => self.io[(addr - 0xFF00) as usize],                // その他のI/Oレジスタ
+        }
+    }
+
+    // MBC3 でRTCレジスタが割り当てられている間はRAMの代わりにRTCを読み書きする
+    fn read_eram(&self, addr: u16) -> u8 {
+        if let Some(value) = self.mbc.read_rtc() {
+            return value;
+        }
+        self.eram_index(addr).map_or(0xFF, |i| self.eram[i])
+    }
+
+    fn write_eram(&mut self, addr: u16, value: u8) {
+        if self.mbc.write_rtc(value) {
+            return;
+        }
+        if let Some(index) = self.eram_index(addr) {
+            self.eram[index] = value;
+        }
+    }
+
+    // 外部RAMのインデックス (無効化中・RAMなしは None)
+    // RAMより大きいバンク番号やアドレスはRAMのサイズで折り返す
+    fn eram_index(&self, addr: u16) -> Option<usize> {
+        if !self.mbc.ram_enabled() || self.eram.is_empty() {
+            return None;
+        }
+        let index = self.mbc.ram_bank() as usize * 0x2000 + (addr - 0xA000) as usize;
+        Some(index % self.eram.len())
+    }
+
+    // 16bit読み出し (リトルエンディアン: addr が下位, addr+1 が上位)
+    pub fn read_word(&mut self, addr: u16) -> u16 {
+        let low = self.read_byte(addr);
+        let high = self.read_byte(addr.wrapping_add(1));
+        ((high as u16) << 8) | (low as u16)
+    }
+
+    // 16bit書き込み (下位バイト, 上位バイトの順に書く)
+    pub fn write_word(&mut self, addr: u16, value: u16) {
+        self.write_byte(addr, (value & 0xFF) as u8);
+        self.write_byte(addr.wrapping_add(1), (value >> 8) as u8);
+    }
+
     // Write Memory
     pub fn write_byte(&mut self, addr: u16, value: u8) {
+        self.log_access(addr, value, AccessKind::Write);
+        if self.blocked_by_dma(addr) {
+            eprintln!("Warning: Write during OAM DMA: 0x{:04X}", addr);
+            return;
+        }
+        self.store(addr, value);
+    }
+
+    // I/Oレジスタ (0xFF00 + offset) の読み出し (PPU/APU/Timer などの周辺用)
+    // offset は 0x00 - 0x7F (0x80 以降は HRAM なので呼び出し側の誤り)
+    pub fn read_io(&self, offset: u8) -> u8 {
+        debug_assert!(offset < 0x80, "I/O offset out of range: 0x{:02X}", offset);
+        self.peek(0xFF00 | offset as u16)
+    }
+
+    // I/Oレジスタ (0xFF00 + offset) への書き込み (アクセス履歴には残さない)
+    pub fn write_io(&mut self, offset: u8, value: u8) {
+        debug_assert!(offset < 0x80, "I/O offset out of range: 0x{:02X}", offset);
+        self.store(0xFF00 | offset as u16, value);
+    }
+
+    // 各領域への書き込み (未定義領域は警告のみ)
+    fn store(&mut self, addr: u16, value: u8) {
+        if !self.model.is_cgb() && Self::is_cgb_register(addr) {
+            return; // DMGではCGB専用レジスタへの書き込みは無視
+        }
         match addr {
-            0xC000..=0xDFFF => self.wram[(addr - 0xC000) as usize] = value, // WRAM
-            0xE000..=0xFDFF => self.wram[(addr - 0xE000) as usize] = value, // WRAM mirror
+            0x0000..=0x7FFF => self.mbc.write_byte(addr, value), // バンク切り替え
+            0x8000..=0x9FFF => self.vram[self.vbk as usize][(addr - 0x8000) as usize] = value, // VRAM
+            0xA000..=0xBFFF => self.write_eram(addr, value), // 外部RAM / RTC
+            0xC000..=0xDFFF => {
+                let (bank, offset) = self.wram_index(addr - 0xC000); // WRAM
+                self.wram[bank][offset] = value;
+            }
+            0xE000..=0xFDFF => {
+                let (bank, offset) = self.wram_index(addr - 0xE000); // WRAM mirror
+                self.wram[bank][offset] = value;
+            }
+            0xFE00..=0xFE9F => self.oam[(addr - 0xFE00) as usize] = value, // OAM
+            0xFF00..=0xFF7F => self.store_io(addr, value),                 // I/Oレジスタ
             0xFF80..=0xFFFE => self.hram[(addr - 0xFF80) as usize] = value, // HRAM
-            0xFFFF => self.ie = value,                                      // 割り込みレジスタ
-            0xFF0F => self.interrupt_flag = value,                          // 割り込みフラグ
+            0xFFFF => self.ie = InterruptFlags(value),                     // 割り込みレジスタ
             _ => {
                 eprintln!("Wraning: Write to unmapped memory: 0x{:04X}", addr);
             }
         }
     }
+
+    // I/Oレジスタ (0xFF00 - 0xFF7F) への書き込み (個別に処理しないものは io 配列)
+    fn store_io(&mut self, addr: u16, value: u8) {
+        match addr {
+            0xFF00 => self.joypad.write(value), // ジョイパッド
+            0xFF01 => self.sb = value,          // シリアルデータ
+            0xFF02 => {
+                // シリアル制御
+                self.sc = value;
+                if value & 0x81 == 0x81 {
+                    self.serial_transfer();
+                }
+            }
+            0xFF4D => self.key1 = (self.key1 & 0x80) | (value & 0x01), // KEY1
+            0xFF4F => self.vbk = value & 0x01,                         // VRAMバンク選択
+            0xFF68 => self.bg_palette.write_spec(value),               // BCPS
+            0xFF69 => self.bg_palette.write_data(value),               // BCPD
+            0xFF6A => self.obj_palette.write_spec(value),              // OCPS
+            0xFF6B => self.obj_palette.write_data(value),              // OCPD
+            0xFF70 => self.svbk = value & 0x07,                        // WRAMバンク選択
+            0xFF0F => self.interrupt_flag = InterruptFlags(value),     // 割り込みフラグ
+            0xFF10..=0xFF14 => self.apu.write(addr, value),            // チャンネル1 (NR10 - NR14)
+            0xFF26 => self.apu.write(addr, value),                     // NR52 (サウンド電源)
+            0xFF30..=0xFF3F => self.apu.write(addr, value),            // 波形RAM
+            0xFF46 => {
+                // OAM DMA (値は LCDレジスタとして読み返せる)
+                self.lcd[0x06] = value;
+                self.start_oam_dma(value);
+            }
+            0xFF40..=0xFF4B => self.lcd[(addr - 0xFF40) as usize] = value, // LCDレジスタ
+            _ => self.io[(addr - 0xFF00) as usize] = value,                // その他のI/Oレジスタ
+        }
+    }
+}
+
+// バンク分けされたメモリ (WRAM, VRAM) はバンクを連結したバイト列として保存する
+#[cfg(feature = "serde")]
+mod banks_serde {
+    use serde::de::Error;
+    use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+    pub fn serialize<S: Serializer, const N: usize, const B: usize>(
+        banks: &[[u8; N]; B],
+        s: S,
+    ) -> Result<S::Ok, S::Error> {
+        banks.as_flattened().serialize(s)
+    }
+
+    pub fn deserialize<'de, D: Deserializer<'de>, const N: usize, const B: usize>(
+        d: D,
+    ) -> Result<Box<[[u8; N]; B]>, D::Error> {
+        let bytes = Vec::<u8>::deserialize(d)?;
+        if bytes.len() != N * B {
+            return Err(D::Error::invalid_length(
+                bytes.len(),
+                &"banked memory bytes",
+            ));
+        }
+        let mut banks = Box::new([[0; N]; B]);
+        banks.as_flattened_mut().copy_from_slice(&bytes);
+        Ok(banks)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> MMU {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0000] = 0x11;
+        rom[0x7FFF] = 0x22;
+        MMU::new(rom)
+    }
+
+    #[test]
+    fn peek_matches_read_byte() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x33);
+        mmu.write_byte(0xFF80, 0x44);
+        for addr in [0x0000, 0x7FFF, 0xC000, 0xE000, 0xFF80] {
+            assert_eq!(mmu.peek(addr), mmu.read_byte(addr));
+        }
+        assert_eq!(mmu.peek(0xFEA0), 0xFF); // 未定義領域も警告なしで 0xFF
+    }
+
+    #[test]
+    fn oam_read_write() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFE00, 0x12);
+        mmu.write_byte(0xFE9F, 0x34);
+        assert_eq!(mmu.read_byte(0xFE00), 0x12);
+        assert_eq!(mmu.oam()[0x9F], 0x34);
+        assert_eq!(mmu.read_byte(0xFEA0), 0xFF); // OAMの直後は使用禁止領域
+    }
+
+    #[test]
+    fn access_log_records_reads_and_writes() {
+        let mut mmu = setup();
+        mmu.enable_access_log(8);
+        mmu.set_cycle(12);
+        mmu.write_byte(0xC000, 0x5A);
+        mmu.set_cycle(16);
+        assert_eq!(mmu.read_byte(0xC000), 0x5A);
+        assert_eq!(
+            mmu.drain_log(),
+            vec![
+                AccessEntry {
+                    cycle: 12,
+                    addr: 0xC000,
+                    value: 0x5A,
+                    kind: AccessKind::Write,
+                },
+                AccessEntry {
+                    cycle: 16,
+                    addr: 0xC000,
+                    value: 0x5A,
+                    kind: AccessKind::Read,
+                },
+            ]
+        );
+        assert!(mmu.drain_log().is_empty()); // drain_log で空になる
+    }
+
+    #[test]
+    fn access_log_stops_at_max_entries() {
+        let mut mmu = setup();
+        mmu.enable_access_log(2);
+        for addr in 0xC000..0xC004 {
+            mmu.write_byte(addr, addr as u8);
+        }
+        let addrs: Vec<u16> = mmu.drain_log().iter().map(|e| e.addr).collect();
+        assert_eq!(addrs, vec![0xC000, 0xC001]);
+
+        mmu.write_byte(0xC004, 0x04); // 取り出した分だけ再び記録できる
+        assert_eq!(mmu.drain_log().len(), 1);
+    }
+
+    #[test]
+    fn access_log_ignores_peek_and_disabled_log() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x01); // 有効化前は記録しない
+        mmu.enable_access_log(4);
+        mmu.peek(0xC000);
+        assert!(mmu.drain_log().is_empty());
+
+        mmu.read_byte(0xC000);
+        mmu.disable_access_log();
+        mmu.read_byte(0xC000);
+        assert!(mmu.drain_log().is_empty()); // 無効化で記録も破棄される
+    }
+
+    #[test]
+    fn mbc1_mode1_banks_rom_bank0_window() {
+        // 1MB の MBC1 ROM: 各バンクの先頭にバンク番号を書いておく
+        let mut rom = vec![0x00; 0x4000 * 64];
+        for bank in 0..64 {
+            rom[bank * 0x4000] = bank as u8;
+        }
+        rom[0x0147] = 0x01;
+        let mut mmu = MMU::new(rom);
+
+        mmu.write_byte(0x2000, 0x00); // 0 は 1 として扱う
+        mmu.write_byte(0x4000, 0x01);
+        assert_eq!(mmu.read_byte(0x0000), 0x00); // モード0: 低位側はバンク0
+        assert_eq!(mmu.read_byte(0x4000), 0x21);
+
+        mmu.write_byte(0x6000, 0x01);
+        assert_eq!(mmu.read_byte(0x0000), 0x20); // モード1: 低位側もバンク 0x20
+        assert_eq!(mmu.read_byte(0x4000), 0x21);
+    }
+
+    #[test]
+    fn mbc1_reads_seven_bit_bank_from_2mb_rom() {
+        let mut rom = vec![0x00; 0x4000 * 128];
+        rom[0x0147] = 0x01;
+        rom[0x4000] = 0x01; // バンク1 の先頭
+        rom[0x7F * 0x4000] = 0x7F;
+        let mut mmu = MMU::new(rom);
+
+        mmu.write_byte(0x2000, 0x00);
+        assert_eq!(mmu.read_byte(0x4000), 0x01); // 0 は 1
+        mmu.write_byte(0x2000, 0x1F);
+        mmu.write_byte(0x4000, 0x03);
+        assert_eq!(mmu.read_byte(0x4000), 0x7F);
+    }
+
+    #[test]
+    fn dmg_cgb_registers_read_ff() {
+        let mut mmu = setup();
+        assert_eq!(mmu.model(), GameBoyModel::Dmg);
+        for addr in [
+            0xFF4D, 0xFF4F, 0xFF51, 0xFF52, 0xFF53, 0xFF54, 0xFF55, 0xFF56, 0xFF68, 0xFF6B, 0xFF70,
+        ] {
+            mmu.write_byte(addr, 0x01); // 書き込みは無視
+            assert_eq!(mmu.read_byte(addr), 0xFF);
+            assert_eq!(mmu.peek(addr), 0xFF);
+        }
+    }
+
+    #[test]
+    fn cgb_registers_read_back_in_cgb_mode() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = 0x80; // CGB対応
+        let mut mmu = MMU::new(rom);
+        assert!(mmu.model().is_cgb());
+        // HDMA1-5 と RP は個別の処理がまだないので io 配列に残る
+        for (i, addr) in (0xFF51..=0xFF56).enumerate() {
+            mmu.write_byte(addr, 0x10 + i as u8);
+            assert_eq!(mmu.read_byte(addr), 0x10 + i as u8, "0x{:04X}", addr);
+            assert_eq!(mmu.peek(addr), 0x10 + i as u8);
+        }
+        mmu.write_byte(0xFF4D, 0x01); // KEY1 は bit0 のみ書ける
+        assert_eq!(mmu.read_byte(0xFF4D), 0x7F);
+        mmu.write_byte(0xFF70, 0x03);
+        assert_eq!(mmu.read_byte(0xFF70), 0xFB);
+    }
+
+    #[test]
+    fn lcd_registers_read_write() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF40, 0x91);
+        mmu.write_byte(0xFF4B, 0x07);
+        assert_eq!(mmu.read_byte(0xFF40), 0x91);
+        assert_eq!(mmu.peek(0xFF4B), 0x07);
+    }
+
+    #[test]
+    fn io_registers_share_read_write_io() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF05, 0x12); // TIMA
+        assert_eq!(mmu.read_io(0x05), 0x12);
+        mmu.write_io(0x05, 0x34);
+        assert_eq!(mmu.read_byte(0xFF05), 0x34);
+        // 個別に処理するレジスタは io 配列で上書きされない
+        mmu.write_io(0x0F, 0x04);
+        assert_eq!(mmu.interrupt_flag(), InterruptFlags::TIMER);
+        mmu.write_byte(0xFF00, 0x20);
+        assert_eq!(mmu.read_io(0x00), mmu.read_byte(0xFF00));
+    }
+
+    #[test]
+    fn io_accessors_match_byte_accessors() {
+        let mut mmu = setup();
+        for offset in [0x01, 0x05, 0x40, 0x42, 0x47, 0x7F] {
+            let addr = 0xFF00 | offset as u16;
+            mmu.write_io(offset, offset ^ 0xA5);
+            assert_eq!(mmu.read_byte(addr), offset ^ 0xA5);
+            mmu.write_byte(addr, offset);
+            assert_eq!(mmu.read_io(offset), offset);
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "I/O offset out of range")]
+    fn read_io_rejects_hram_offsets() {
+        setup().read_io(0x80);
+    }
+
+    #[test]
+    fn request_interrupt_sets_if_bits() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF0F, 0x01);
+        mmu.request_interrupt(InterruptFlags::LCD_STAT);
+        assert_eq!(mmu.read_byte(0xFF0F), 0x03);
+    }
+
+    #[test]
+    fn request_and_clear_interrupt() {
+        let mut mmu = setup();
+        mmu.request_interrupt(InterruptFlags::VBLANK);
+        mmu.request_interrupt(InterruptFlags::TIMER);
+        assert_eq!(mmu.read_byte(0xFF0F), 0x05);
+        mmu.clear_interrupt(InterruptFlags::VBLANK);
+        assert_eq!(mmu.read_byte(0xFF0F), 0x04);
+        mmu.clear_interrupt(InterruptFlags::VBLANK); // 立っていないbitはそのまま
+        assert_eq!(mmu.read_byte(0xFF0F), 0x04);
+    }
+
+    #[test]
+    fn nr52_routes_to_apu() {
+        let mut mmu = setup();
+        assert_eq!(mmu.read_byte(0xFF26), 0xF0);
+        mmu.write_byte(0xFF26, 0x00);
+        assert_eq!(mmu.read_byte(0xFF26), 0x70);
+        assert_eq!(mmu.apu().read(0xFF26), 0x70);
+    }
+
+    #[test]
+    fn oam_dma_copies_160_bytes() {
+        let mut mmu = setup();
+        for i in 0..0xA0 {
+            mmu.write_byte(0xC100 + i, i as u8);
+        }
+        mmu.write_byte(0xFF46, 0xC1);
+        assert!(mmu.in_oam_dma());
+        assert_eq!(mmu.read_byte(0xFF46), 0xC1);
+        mmu.step_dma(0x9F);
+        assert!(mmu.in_oam_dma());
+        mmu.step_dma(4); // 余ったサイクルは捨てる
+        assert!(!mmu.in_oam_dma());
+        assert_eq!(mmu.oam()[0x00], 0x00);
+        assert_eq!(mmu.oam()[0x9F], 0x9F);
+    }
+
+    #[test]
+    fn strict_oam_dma_blocks_all_but_hram() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x12);
+        mmu.write_byte(0xFF80, 0x34);
+        mmu.set_strict_dma_timing(true);
+        mmu.write_byte(0xFF46, 0xC0);
+
+        assert_eq!(mmu.read_byte(0xC000), 0xFF); // WRAM は読めない
+        mmu.write_byte(0xC000, 0x56); // 書き込みも無視
+        assert_eq!(mmu.read_byte(0xFF80), 0x34); // HRAM は読める
+        mmu.write_byte(0xFF81, 0x78);
+        assert_eq!(mmu.read_byte(0xFF81), 0x78);
+
+        mmu.step_dma(0xA0);
+        assert_eq!(mmu.read_byte(0xC000), 0x12);
+    }
+
+    #[test]
+    fn oam_dma_without_strict_timing_allows_access() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x12);
+        mmu.write_byte(0xFF46, 0xC0);
+        assert_eq!(mmu.read_byte(0xC000), 0x12);
+    }
+
+    // 受け取ったバイトを記録し、+1 した値を返す通信相手
+    struct EchoLink(std::rc::Rc<std::cell::RefCell<Vec<u8>>>);
+
+    impl SerialLink for EchoLink {
+        fn transfer(&mut self, byte: u8) -> u8 {
+            self.0.borrow_mut().push(byte);
+            byte.wrapping_add(1)
+        }
+    }
+
+    #[test]
+    fn serial_transfer_exchanges_with_link() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF01, 0x42);
+        mmu.write_byte(0xFF02, 0x81);
+        assert_eq!(mmu.read_byte(0xFF01), 0xFF); // 未接続
+        assert_eq!(mmu.read_byte(0xFF0F) & 0x08, 0x08);
+
+        let received = std::rc::Rc::new(std::cell::RefCell::new(Vec::new()));
+        mmu.connect_serial(Box::new(EchoLink(received.clone())));
+        mmu.write_byte(0xFF01, 0x42);
+        mmu.write_byte(0xFF02, 0x80); // 外部クロックでは始まらない
+        assert_eq!(mmu.read_byte(0xFF02), 0xFE);
+        mmu.write_byte(0xFF02, 0x81);
+        assert_eq!(mmu.read_byte(0xFF01), 0x43);
+        assert_eq!(mmu.read_byte(0xFF02), 0x7F); // 転送完了で bit7 が下りる
+        assert_eq!(*received.borrow(), [0x42]);
+    }
+
+    #[test]
+    fn nr14_trigger_restarts_channel1() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF12, 0xF0); // DAC オン
+        mmu.write_byte(0xFF11, 0x3F); // 長さ 1
+        mmu.apu_mut().channel1.length.counter = 0;
+        assert!(!mmu.apu().channel1.enabled);
+
+        mmu.write_byte(0xFF14, 0x80);
+        let ch1 = &mmu.apu().channel1;
+        assert!(ch1.enabled);
+        assert_eq!(ch1.length.counter, 64);
+        assert_eq!(ch1.envelope.volume(), 15);
+        assert_eq!(mmu.read_byte(0xFF26) & 0x01, 0x01); // NR52 bit0
+    }
+
+    #[test]
+    fn wave_ram_routes_to_apu() {
+        let mut mmu = setup();
+        for i in 0..16 {
+            mmu.write_byte(0xFF30 + i, i as u8 * 0x11);
+        }
+        assert_eq!(mmu.read_byte(0xFF3F), 0xFF);
+
+        // DMGでチャンネル3の再生中は再生位置のバイトしか読めない
+        mmu.apu_mut().wave.channel_enabled = true;
+        mmu.apu_mut().wave.position = 10;
+        assert_eq!(mmu.read_byte(0xFF30), 0x55);
+        assert_eq!(mmu.read_byte(0xFF3F), 0x55);
+    }
+
+    #[test]
+    fn rom_boundaries() {
+        let mut mmu = setup();
+        assert_eq!(mmu.read_byte(0x0000), 0x11);
+        assert_eq!(mmu.read_byte(0x7FFF), 0x22);
+        mmu.write_byte(0x0000, 0x99); // ROMへの書き込みは無視
+        assert_eq!(mmu.read_byte(0x0000), 0x11);
+    }
+
+    #[test]
+    fn vram_boundaries() {
+        let mut mmu = setup();
+        mmu.write_byte(0x8000, 0x01); // LD [HL], A でタイルデータを書く場合と同じ
+        mmu.write_byte(0x9FFF, 0x02);
+        assert_eq!(mmu.read_byte(0x8000), 0x01);
+        assert_eq!(mmu.read_byte(0x9FFF), 0x02);
+        assert_eq!(mmu.read_byte(0x7FFF), 0x22); // VRAMの直前はROM
+    }
+
+    #[test]
+    fn wram_boundaries() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x01);
+        mmu.write_byte(0xDFFF, 0x02);
+        assert_eq!(mmu.read_byte(0xC000), 0x01);
+        assert_eq!(mmu.read_byte(0xDFFF), 0x02);
+        assert_eq!(mmu.read_byte(0xBFFF), 0xFF); // WRAMの直前
+    }
+
+    #[test]
+    fn wram_mirror_boundaries() {
+        let mut mmu = setup();
+        mmu.write_byte(0xC000, 0x03);
+        mmu.write_byte(0xDDFF, 0x04);
+        assert_eq!(mmu.read_byte(0xE000), 0x03);
+        assert_eq!(mmu.read_byte(0xFDFF), 0x04);
+        mmu.write_byte(0xE001, 0x05); // ミラーへの書き込みはWRAMに反映
+        assert_eq!(mmu.read_byte(0xC001), 0x05);
+        mmu.write_byte(0xFE00, 0x06); // ミラーの直後はOAM
+        assert_eq!(mmu.read_byte(0xDE00), 0x00);
+    }
+
+    #[test]
+    fn cgb_wram_banking() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = 0x80; // CGB対応
+        let mut mmu = MMU::new(rom);
+
+        mmu.write_byte(0xC000, 0xAA);
+        mmu.write_byte(0xFF70, 0x07);
+        mmu.write_byte(0xC000, 0xBB); // 0xC000 は常にバンク0
+        mmu.write_byte(0xD000, 0x77);
+        mmu.write_byte(0xFF70, 0x02);
+        mmu.write_byte(0xD000, 0x22);
+        assert_eq!(mmu.read_byte(0xC000), 0xBB);
+        assert_eq!(mmu.read_byte(0xD000), 0x22);
+
+        mmu.write_byte(0xFF70, 0x07);
+        assert_eq!(mmu.read_byte(0xC000), 0xBB);
+        assert_eq!(mmu.read_byte(0xD000), 0x77);
+        assert_eq!(mmu.read_byte(0xF000), 0x77); // ミラーも同じバンク
+
+        mmu.write_byte(0xD000, 0x11);
+        mmu.write_byte(0xFF70, 0x00); // 0 はバンク1
+        mmu.write_byte(0xD000, 0x01);
+        mmu.write_byte(0xFF70, 0x01);
+        assert_eq!(mmu.read_byte(0xD000), 0x01);
+    }
+
+    #[test]
+    fn wram_bank_accessors() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = 0x80; // CGB対応
+        let mut mmu = MMU::new(rom);
+        mmu.write_byte(0xC123, 0x12);
+        mmu.write_byte(0xFF70, 0x03);
+        mmu.write_byte(0xDFFF, 0x34);
+        assert_eq!(mmu.wram_bank(0)[0x123], 0x12);
+        assert_eq!(mmu.wram_bank(3)[0xFFF], 0x34);
+        assert_eq!(mmu.current_wram_bank()[0xFFF], 0x34);
+        mmu.write_byte(0xFF70, 0x00);
+        assert_eq!(mmu.current_wram_bank()[0xFFF], 0x00); // バンク1
+    }
+
+    #[test]
+    fn vram_bank_accessors() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = 0x80; // CGB対応
+        let mut mmu = MMU::new(rom);
+        mmu.write_byte(0x8000, 0x12);
+        mmu.write_byte(0xFF4F, 0x01);
+        assert_eq!(mmu.read_byte(0xFF4F), 0xFF);
+        mmu.write_byte(0x8000, 0x34);
+        assert_eq!(mmu.read_byte(0x8000), 0x34);
+        assert_eq!(mmu.vram_bank(0)[0x0000], 0x12);
+        assert_eq!(mmu.vram_bank(1)[0x0000], 0x34);
+        assert_eq!(mmu.current_vram_bank()[0x0000], 0x34);
+        mmu.write_byte(0xFF4F, 0xFE); // bit0 のみ有効
+        assert_eq!(mmu.read_byte(0xFF4F), 0xFE);
+        assert_eq!(mmu.current_vram_bank()[0x0000], 0x12);
+    }
+
+    #[test]
+    fn dmg_ignores_vbk() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF4F, 0x01);
+        mmu.write_byte(0x8000, 0x56);
+        assert_eq!(mmu.vram_bank(0)[0x0000], 0x56);
+        assert_eq!(mmu.vram_bank(1)[0x0000], 0x00);
+    }
+
+    #[test]
+    fn dmg_ignores_svbk() {
+        let mut mmu = setup();
+        mmu.write_byte(0xD000, 0x01);
+        mmu.write_byte(0xFF70, 0x03);
+        assert_eq!(mmu.read_byte(0xFF70), 0xFF);
+        assert_eq!(mmu.read_byte(0xD000), 0x01);
+    }
+
+    #[test]
+    fn word_access_is_little_endian() {
+        let mut mmu = setup();
+        mmu.write_word(0xC000, 0x1234);
+        assert_eq!(mmu.read_byte(0xC000), 0x34);
+        assert_eq!(mmu.read_byte(0xC001), 0x12);
+        assert_eq!(mmu.read_word(0xC000), 0x1234);
+    }
+
+    #[test]
+    fn hram_boundaries() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFF80, 0x06);
+        mmu.write_byte(0xFFFE, 0x07);
+        assert_eq!(mmu.read_byte(0xFF80), 0x06);
+        assert_eq!(mmu.read_byte(0xFFFE), 0x07);
+        assert_eq!(mmu.read_byte(0xFF7F), 0x00); // HRAMの直前はI/Oレジスタ
+    }
+
+    #[test]
+    fn interrupt_registers() {
+        let mut mmu = setup();
+        mmu.write_byte(0xFFFF, 0x1F);
+        mmu.write_byte(0xFF0F, 0x05);
+        assert_eq!(mmu.read_byte(0xFFFF), 0x1F);
+        assert_eq!(mmu.read_byte(0xFF0F), 0x05);
+        assert_eq!(mmu.read_byte(0xFFFE), 0x00); // IEの直前はHRAM
+    }
+
+    // MBC1 + RAM, 外部RAMサイズコード ram_size_code のカートリッジ
+    fn setup_mbc1_ram(ram_size_code: u8) -> MMU {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0147] = 0x02; // MBC1+RAM
+        rom[0x0149] = ram_size_code;
+        MMU::new(rom)
+    }
+
+    #[test]
+    fn eram_sized_from_header() {
+        for (code, size) in [(0x00, 0), (0x02, 0x2000), (0x03, 0x8000), (0x04, 0x20000)] {
+            assert_eq!(setup_mbc1_ram(code).eram.len(), size, "code 0x{:02X}", code);
+        }
+    }
+
+    #[test]
+    fn rom_ram_eram_maps_linearly() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0147] = 0x08; // ROM+RAM (MBCなし)
+        rom[0x0149] = 0x02; // 8KB
+        let mut mmu = MMU::new(rom);
+        mmu.write_byte(0xA000, 0x12); // 有効化は不要
+        mmu.write_byte(0xBFFF, 0x34);
+        assert_eq!(mmu.read_byte(0xA000), 0x12);
+        assert_eq!(mmu.read_byte(0xBFFF), 0x34);
+        assert_eq!((mmu.eram[0x0000], mmu.eram[0x1FFF]), (0x12, 0x34));
+    }
+
+    #[test]
+    fn eram_needs_enable() {
+        let mut mmu = setup_mbc1_ram(0x02);
+        mmu.write_byte(0xA000, 0x12);
+        assert_eq!(mmu.read_byte(0xA000), 0xFF); // 無効化中
+        mmu.write_byte(0x0000, 0x0A);
+        mmu.write_byte(0xBFFF, 0x34);
+        assert_eq!(mmu.read_byte(0xBFFF), 0x34);
+        mmu.write_byte(0x0000, 0x00);
+        assert_eq!(mmu.read_byte(0xBFFF), 0xFF);
+        mmu.write_byte(0x0000, 0x0A);
+        assert_eq!(mmu.read_byte(0xBFFF), 0x34); // 無効化しても内容は残る
+    }
+
+    #[test]
+    fn eram_banks_in_mbc1_mode1() {
+        let mut mmu = setup_mbc1_ram(0x03); // 32KB = 4バンク
+        mmu.write_byte(0x0000, 0x0A);
+        mmu.write_byte(0x6000, 0x01); // モード1
+        for bank in 0..4 {
+            mmu.write_byte(0x4000, bank);
+            mmu.write_byte(0xA000, 0x10 + bank);
+        }
+        for bank in 0..4 {
+            mmu.write_byte(0x4000, bank);
+            assert_eq!(mmu.read_byte(0xA000), 0x10 + bank);
+        }
+
+        // モード0 では常にバンク0
+        mmu.write_byte(0x6000, 0x00);
+        assert_eq!(mmu.read_byte(0xA000), 0x10);
+    }
+
+    #[test]
+    fn mbc3_maps_ram_banks_and_rtc() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0147] = 0x10; // MBC3+TIMER+RAM+BATTERY
+        rom[0x0149] = 0x03; // 32KB
+        let mut mmu = MMU::new(rom);
+        mmu.write_byte(0x0000, 0x0A);
+        mmu.write_byte(0x4000, 0x03);
+        mmu.write_byte(0xA000, 0x33);
+
+        mmu.write_byte(0x4000, 0x0A); // RTC 時
+        mmu.write_byte(0xA000, 0x17);
+        mmu.write_byte(0x6000, 0x00);
+        mmu.write_byte(0x6000, 0x01);
+        assert_eq!(mmu.read_byte(0xA000), 0x17);
+        assert_eq!(mmu.read_byte(0xBFFF), 0x17); // 領域全体が同じレジスタ
+
+        mmu.write_byte(0x4000, 0x03);
+        assert_eq!(mmu.read_byte(0xA000), 0x33); // RTC への書き込みはRAMに届かない
+    }
+
+    #[test]
+    fn unmapped_regions_read_ff() {
+        let mut mmu = setup();
+        mmu.write_byte(0xA000, 0x12); // 外部RAMなし (ヘッダ 0x0149 = 0)
+        mmu.write_byte(0xFEA0, 0x34); // 使用禁止領域
+        assert_eq!(mmu.read_byte(0xA000), 0xFF);
+        assert_eq!(mmu.read_byte(0xFEA0), 0xFF);
+    }
 }
@@ -0,0 +1,72 @@
+// エミュレートするハードウェアの種類
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum GameBoyModel {
+    Dmg, // Game Boy
+    Cgb, // Game Boy Color
+    Sgb, // Super Game Boy
+}
+
+impl GameBoyModel {
+    // ROMヘッダから判定する
+    // 0x0143: CGBフラグ (0x80 = CGB対応, 0xC0 = CGB専用)
+    // 0x0146: SGBフラグ (0x03 = SGB対応)
+    pub fn from_rom(rom: &[u8]) -> Self {
+        let cgb_flag = rom.get(0x0143).copied().unwrap_or(0);
+        let sgb_flag = rom.get(0x0146).copied().unwrap_or(0);
+        if cgb_flag & 0x80 != 0 {
+            GameBoyModel::Cgb
+        } else if sgb_flag == 0x03 {
+            GameBoyModel::Sgb
+        } else {
+            GameBoyModel::Dmg
+        }
+    }
+
+    pub fn is_cgb(&self) -> bool {
+        *self == GameBoyModel::Cgb
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rom_with_flags(cgb_flag: u8, sgb_flag: u8) -> Vec<u8> {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = cgb_flag;
+        rom[0x0146] = sgb_flag;
+        rom
+    }
+
+    #[test]
+    fn from_rom_reads_cgb_flag() {
+        assert_eq!(
+            GameBoyModel::from_rom(&rom_with_flags(0x80, 0x00)),
+            GameBoyModel::Cgb
+        );
+        assert_eq!(
+            GameBoyModel::from_rom(&rom_with_flags(0xC0, 0x00)),
+            GameBoyModel::Cgb
+        );
+        assert_eq!(
+            GameBoyModel::from_rom(&rom_with_flags(0x00, 0x00)),
+            GameBoyModel::Dmg
+        );
+        // CGB対応ならSGBフラグより優先
+        assert_eq!(
+            GameBoyModel::from_rom(&rom_with_flags(0x80, 0x03)),
+            GameBoyModel::Cgb
+        );
+        assert_eq!(
+            GameBoyModel::from_rom(&rom_with_flags(0x00, 0x03)),
+            GameBoyModel::Sgb
+        );
+    }
+
+    #[test]
+    fn from_rom_short_rom_is_dmg() {
+        assert_eq!(GameBoyModel::from_rom(&[]), GameBoyModel::Dmg);
+        assert_eq!(GameBoyModel::from_rom(&[0x00; 0x0143]), GameBoyModel::Dmg);
+    }
+}
@@ -0,0 +1,134 @@
+// DMG液晶の実測に近い4階調 (0 = 最も明るい, 3 = 最も暗い)
+pub const DMG_GREEN: Palette = Palette {
+    colors: [
+        (0xE0, 0xF8, 0x70),
+        (0x88, 0xB0, 0x10),
+        (0x30, 0x62, 0x30),
+        (0x00, 0x30, 0x10),
+    ],
+};
+
+// 白黒4階調
+pub const GRAYSCALE: Palette = Palette {
+    colors: [
+        (0xFF, 0xFF, 0xFF),
+        (0xAA, 0xAA, 0xAA),
+        (0x55, 0x55, 0x55),
+        (0x00, 0x00, 0x00),
+    ],
+};
+
+// 2bitの階調値から表示色への対応表
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Palette {
+    pub colors: [(u8, u8, u8); 4],
+}
+
+impl Palette {
+    pub fn new(colors: [(u8, u8, u8); 4]) -> Self {
+        Self { colors }
+    }
+
+    // 階調 (0-3) を RGB888 に変換 (上位bitは無視)
+    pub fn to_rgb888(&self, shade: u8) -> (u8, u8, u8) {
+        self.colors[(shade & 0x03) as usize]
+    }
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        DMG_GREEN
+    }
+}
+
+// CGBのパレットRAM (BCPS/BCPD, OCPS/OCPD)
+// 8パレット x 4色 x 2バイト (RGB555, リトルエンディアン)
+#[derive(Debug, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GbcPaletteRam {
+    #[cfg_attr(feature = "serde", serde(with = "serde_big_array::BigArray"))]
+    pub data: [u8; 64],
+    pub index: u8,            // BCPS/OCPS bit5-0
+    pub auto_increment: bool, // BCPS/OCPS bit7
+}
+
+impl GbcPaletteRam {
+    pub fn new() -> Self {
+        Self {
+            data: [0; 64],
+            index: 0,
+            auto_increment: false,
+        }
+    }
+
+    // BCPS/OCPS への書き込み
+    pub fn write_spec(&mut self, value: u8) {
+        self.index = value & 0x3F;
+        self.auto_increment = value & 0x80 != 0;
+    }
+
+    pub fn read_spec(&self) -> u8 {
+        ((self.auto_increment as u8) << 7) | 0x40 | self.index
+    }
+
+    // BCPD/OCPD への書き込み (自動インクリメントは 0x40 で折り返す)
+    pub fn write_data(&mut self, value: u8) {
+        self.data[(self.index & 0x3F) as usize] = value;
+        if self.auto_increment {
+            self.index = (self.index + 1) & 0x3F;
+        }
+    }
+
+    pub fn read_data(&self) -> u8 {
+        self.data[(self.index & 0x3F) as usize]
+    }
+}
+
+impl Default for GbcPaletteRam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn palette_maps_shades_to_rgb() {
+        assert_eq!(DMG_GREEN.to_rgb888(0), (0xE0, 0xF8, 0x70));
+        assert_eq!(DMG_GREEN.to_rgb888(3), (0x00, 0x30, 0x10));
+        assert_eq!(GRAYSCALE.to_rgb888(1), (0xAA, 0xAA, 0xAA));
+        assert_eq!(GRAYSCALE.to_rgb888(2), (0x55, 0x55, 0x55));
+        assert_eq!(Palette::default(), DMG_GREEN);
+    }
+
+    #[test]
+    fn palette_ignores_upper_shade_bits() {
+        assert_eq!(GRAYSCALE.to_rgb888(4), GRAYSCALE.to_rgb888(0));
+        assert_eq!(GRAYSCALE.to_rgb888(0xFF), GRAYSCALE.to_rgb888(3));
+    }
+
+    #[test]
+    fn gbc_palette_auto_increment_fills_palette0() {
+        let mut ram = GbcPaletteRam::new();
+        ram.write_spec(0x80); // index 0, 自動インクリメント
+        for i in 0..8 {
+            ram.write_data(0x10 + i);
+        }
+        assert_eq!(
+            &ram.data[0..8],
+            &[0x10, 0x11, 0x12, 0x13, 0x14, 0x15, 0x16, 0x17]
+        );
+        assert_eq!(ram.index, 8);
+    }
+
+    #[test]
+    fn gbc_palette_index_wraps_at_64() {
+        let mut ram = GbcPaletteRam::new();
+        ram.write_spec(0x80 | 0x3F);
+        ram.write_data(0xAA);
+        assert_eq!(ram.data[63], 0xAA);
+        assert_eq!(ram.read_spec(), 0xC0);
+    }
+}
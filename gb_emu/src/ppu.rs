@@ -0,0 +1,676 @@
+use crate::interrupt::InterruptFlags;
+use crate::mmu::MMU;
+
+// 1ライン = 456ドット, 1フレーム = 154ライン (144 表示 + 10 VBlank)
+const DOTS_PER_LINE: u16 = 456;
+const OAM_SCAN_DOTS: u16 = 80;
+const PIXEL_TRANSFER_DOTS: u16 = 172; // 実機はスプライト数などで伸びる
+const VISIBLE_LINES: u8 = 144;
+const LINES_PER_FRAME: u8 = 154;
+const SPRITES_PER_LINE: usize = 10;
+// I/Oレジスタのオフセット (0xFF00 + offset)
+const LCDC: u8 = 0x40;
+const STAT: u8 = 0x41;
+const LY: u8 = 0x44;
+const LYC: u8 = 0x45;
+
+// PPUのモード (値は STAT bit1-0 と同じ)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum PpuMode {
+    HBlank = 0,
+    VBlank = 1,
+    OamScan = 2,
+    PixelTransfer = 3,
+}
+
+// LY の値 (0-153)
+// next() で 153 の次は 0 に戻るので、154 以上にはならない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Scanline(pub u8);
+
+impl Scanline {
+    // LY 144 - 153
+    pub fn is_vblank(&self) -> bool {
+        self.0 >= VISIBLE_LINES
+    }
+
+    // LY 0 - 143
+    pub fn is_visible(&self) -> bool {
+        self.0 < VISIBLE_LINES
+    }
+
+    pub fn next(&self) -> Scanline {
+        Scanline((self.0 + 1) % LINES_PER_FRAME)
+    }
+}
+
+// tick で起きた出来事 (割り込み要求の元になる)
+// MMU への反映は step が行うので、tick 単体では MMU に触らない
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct PpuEvents(pub u8);
+
+impl PpuEvents {
+    pub const VBLANK: PpuEvents = PpuEvents(0x01); // LY 144 に入った
+    pub const STAT_MODE0: PpuEvents = PpuEvents(0x02); // HBlank に入った
+    pub const STAT_MODE2: PpuEvents = PpuEvents(0x04); // OAMスキャンに入った
+    pub const STAT_LYC: PpuEvents = PpuEvents(0x08); // LY == LYC になった
+
+    pub fn contains(&self, events: PpuEvents) -> bool {
+        self.0 & events.0 == events.0
+    }
+
+    pub fn insert(&mut self, events: PpuEvents) {
+        self.0 |= events.0;
+    }
+}
+
+// OAM エントリの属性 (byte3)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SpriteFlags(pub u8);
+
+impl SpriteFlags {
+    // DMG: 0 = OBP0, 1 = OBP1
+    pub fn palette(&self) -> u8 {
+        (self.0 >> 4) & 0x01
+    }
+
+    pub fn x_flip(&self) -> bool {
+        self.0 & 0x20 != 0
+    }
+
+    pub fn y_flip(&self) -> bool {
+        self.0 & 0x40 != 0
+    }
+
+    // true なら背景色 1-3 の下に隠れる
+    pub fn bg_priority(&self) -> bool {
+        self.0 & 0x80 != 0
+    }
+
+    // CGB: タイルデータのVRAMバンク
+    pub fn vram_bank(&self) -> u8 {
+        (self.0 >> 3) & 0x01
+    }
+
+    // CGB: OBJパレット番号 (0-7)
+    pub fn cgb_palette(&self) -> u8 {
+        self.0 & 0x07
+    }
+}
+
+// FIFO を流れる1ピクセル
+// colour は 0-3 の色番号, palette は BG/OBP の番号 (CGB ではパレット番号 0-7)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Pixel {
+    pub colour: u8,
+    pub palette: u8,
+    pub is_sprite: bool,
+    pub bg_priority: bool, // スプライトの OAM 属性 bit7
+}
+
+impl Pixel {
+    pub fn bg(colour: u8, palette: u8) -> Self {
+        Self {
+            colour,
+            palette,
+            is_sprite: false,
+            bg_priority: false,
+        }
+    }
+
+    pub fn sprite(colour: u8, flags: SpriteFlags, is_cgb: bool) -> Self {
+        Self {
+            colour,
+            palette: if is_cgb {
+                flags.cgb_palette()
+            } else {
+                flags.palette()
+            },
+            is_sprite: true,
+            bg_priority: flags.bg_priority(),
+        }
+    }
+}
+
+// BG FIFO とスプライト FIFO の先頭ピクセルを合成する
+// スプライトの色0 は透明, BG優先のスプライトは背景色 1-3 の下に隠れる
+pub fn mix(bg: Pixel, sprite: Pixel) -> Pixel {
+    if sprite.colour == 0 || (sprite.bg_priority && bg.colour != 0) {
+        bg
+    } else {
+        sprite
+    }
+}
+
+// OAM の1エントリ (4バイト)
+// y, x は画面座標 + 16, + 8 のまま保持する
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Sprite {
+    pub y: u8,
+    pub x: u8,
+    pub tile_index: u8,
+    pub flags: SpriteFlags,
+}
+
+impl Sprite {
+    pub fn from_oam(entry: &[u8]) -> Self {
+        Self {
+            y: entry[0],
+            x: entry[1],
+            tile_index: entry[2],
+            flags: SpriteFlags(entry[3]),
+        }
+    }
+}
+
+// 背景/ウィンドウのタイルマップ (32 x 32 タイル)
+// タイル番号は VRAM, CGB属性は VRAMバンク1 の同じ位置にある
+#[derive(Debug, Clone, PartialEq)]
+pub struct TileMap {
+    pub base: u16,                  // 0x9800 または 0x9C00
+    pub tiles: [[u8; 32]; 32],      // [行][列] のタイル番号
+    pub attributes: [[u8; 32]; 32], // [行][列] の属性 (DMGでは常に0)
+}
+
+#[allow(clippy::upper_case_acronyms)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PPU {
+    mode: PpuMode,
+    ly: Scanline,                                     // 現在のライン (0-153)
+    dot: u16,                                         // ライン内のドット (0-455)
+    lyc: u8,                                          // step の開始時に 0xFF45 から読む
+    line_sprites: [Option<Sprite>; SPRITES_PER_LINE], // 現在のラインに表示するスプライト
+}
+
+impl PPU {
+    pub fn new() -> Self {
+        Self {
+            mode: PpuMode::OamScan,
+            ly: Scanline(0),
+            dot: 0,
+            lyc: 0,
+            line_sprites: [None; SPRITES_PER_LINE],
+        }
+    }
+
+    pub fn mode(&self) -> PpuMode {
+        self.mode
+    }
+
+    // フロントエンドがフレームを表示してよいか (LY 144 - 153)
+    pub fn in_vblank(&self) -> bool {
+        self.ly.is_vblank()
+    }
+
+    pub fn ly(&self) -> u8 {
+        self.ly.0
+    }
+
+    pub fn current_scanline(&self) -> Scanline {
+        self.ly
+    }
+
+    pub fn dot(&self) -> u16 {
+        self.dot
+    }
+
+    // index 0: 0x9800, 1: 0x9C00 のタイルマップを読み出す (デバッガ用)
+    pub fn get_tile_map(&self, mmu: &MMU, index: u8) -> TileMap {
+        debug_assert!(index < 2, "tile map index out of range: {}", index);
+        let base: u16 = if index == 0 { 0x9800 } else { 0x9C00 };
+        let offset = (base - 0x8000) as usize;
+        let mut map = TileMap {
+            base,
+            tiles: [[0; 32]; 32],
+            attributes: [[0; 32]; 32],
+        };
+        for row in 0..32 {
+            let start = offset + row * 32;
+            map.tiles[row].copy_from_slice(&mmu.vram_bank(0)[start..start + 32]);
+            map.attributes[row].copy_from_slice(&mmu.vram_bank(1)[start..start + 32]);
+        }
+        map
+    }
+
+    // OAM の40エントリをスプライトとして読み出す
+    pub fn sprites(&self, mmu: &MMU) -> [Sprite; 40] {
+        let mut sprites = [Sprite::default(); 40];
+        for (sprite, entry) in sprites.iter_mut().zip(mmu.oam().chunks_exact(4)) {
+            *sprite = Sprite::from_oam(entry);
+        }
+        sprites
+    }
+
+    pub fn line_sprites(&self) -> &[Option<Sprite>; SPRITES_PER_LINE] {
+        &self.line_sprites
+    }
+
+    // ly に掛かるスプライトを OAM の順に最大10個選ぶ (X座標は見ない)
+    // 11個目以降はそのラインでは表示されない, 高さは LCDC bit2 (0: 8x8, 1: 8x16)
+    pub fn scan_sprites_for_line(&self, mmu: &MMU, ly: u8) -> [Option<Sprite>; SPRITES_PER_LINE] {
+        let height: u16 = if mmu.read_io(LCDC) & 0x04 != 0 { 16 } else { 8 };
+        let line = ly as u16 + 16; // OAM の y は画面座標 + 16
+        let mut selected = [None; SPRITES_PER_LINE];
+        let on_line = self
+            .sprites(mmu)
+            .into_iter()
+            .filter(|sprite| (sprite.y as u16..sprite.y as u16 + height).contains(&line));
+        for (slot, sprite) in selected.iter_mut().zip(on_line) {
+            *slot = Some(sprite);
+        }
+        selected
+    }
+
+    // CPUが消費したサイクル数だけ進める (1サイクル = 1ドット)
+    // tick のイベントを割り込み要求に変え、進めた後の LY と STAT のモードbitを書き戻す
+    pub fn step(&mut self, mmu: &mut MMU, cycles: u8) {
+        self.lyc = mmu.read_io(LYC);
+        for _ in 0..cycles {
+            let events = self.tick();
+            if events.contains(PpuEvents::VBLANK) {
+                mmu.request_interrupt(InterruptFlags::VBLANK);
+            }
+            // STAT bit3: モード0, bit5: モード2, bit6: LYC一致 で STAT 割り込み
+            let stat = mmu.read_io(STAT);
+            if (events.contains(PpuEvents::STAT_MODE0) && stat & 0x08 != 0)
+                || (events.contains(PpuEvents::STAT_MODE2) && stat & 0x20 != 0)
+                || (events.contains(PpuEvents::STAT_LYC) && stat & 0x40 != 0)
+            {
+                mmu.request_interrupt(InterruptFlags::LCD_STAT);
+            }
+            // 表示ラインの開始 (OAMスキャン) でスプライトを選ぶ
+            if events.contains(PpuEvents::STAT_MODE2) {
+                self.line_sprites = self.scan_sprites_for_line(mmu, self.ly.0);
+            }
+        }
+        mmu.write_io(LY, self.ly.0);
+        let stat = mmu.read_io(STAT); // CPUが書いた割り込み許可bitは残す
+        mmu.write_io(STAT, (stat & !0x03) | self.mode as u8);
+    }
+
+    // 1ドット進める
+    fn tick(&mut self) -> PpuEvents {
+        let mut events = PpuEvents::default();
+        self.dot += 1;
+        if self.dot == DOTS_PER_LINE {
+            self.dot = 0;
+            self.ly = self.ly.next();
+            if self.ly == Scanline(VISIBLE_LINES) {
+                events.insert(PpuEvents::VBLANK);
+            }
+            if self.ly.0 == self.lyc {
+                events.insert(PpuEvents::STAT_LYC);
+            }
+        }
+        let mode = if self.ly.is_vblank() {
+            PpuMode::VBlank
+        } else if self.dot < OAM_SCAN_DOTS {
+            PpuMode::OamScan
+        } else if self.dot < OAM_SCAN_DOTS + PIXEL_TRANSFER_DOTS {
+            PpuMode::PixelTransfer
+        } else {
+            PpuMode::HBlank
+        };
+        if mode != self.mode {
+            match mode {
+                PpuMode::HBlank => events.insert(PpuEvents::STAT_MODE0),
+                PpuMode::OamScan => events.insert(PpuEvents::STAT_MODE2),
+                _ => {}
+            }
+        }
+        self.mode = mode;
+        events
+    }
+}
+
+impl Default for PPU {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn setup() -> (PPU, MMU) {
+        (PPU::new(), MMU::new(vec![0x00; 0x8000]))
+    }
+
+    // 指定ドット数だけ進める (step は u8 なので分割する)
+    fn advance(ppu: &mut PPU, mmu: &mut MMU, dots: u32) {
+        for _ in 0..dots / 4 {
+            ppu.step(mmu, 4);
+        }
+        ppu.step(mmu, (dots % 4) as u8);
+    }
+
+    #[test]
+    fn modes_within_a_scanline() {
+        let (mut ppu, mut mmu) = setup();
+        assert_eq!(ppu.mode(), PpuMode::OamScan);
+        advance(&mut ppu, &mut mmu, 80);
+        assert_eq!(ppu.mode(), PpuMode::PixelTransfer);
+        advance(&mut ppu, &mut mmu, 172);
+        assert_eq!(ppu.mode(), PpuMode::HBlank);
+        advance(&mut ppu, &mut mmu, 204);
+        assert_eq!((ppu.mode(), ppu.ly(), ppu.dot()), (PpuMode::OamScan, 1, 0));
+    }
+
+    #[test]
+    fn step_publishes_ly_and_stat() {
+        let (mut ppu, mut mmu) = setup();
+        mmu.write_byte(0xFF41, 0x48); // STAT の割り込み許可bitは残す
+        advance(&mut ppu, &mut mmu, 80);
+        assert_eq!(mmu.read_byte(0xFF41), 0x4B); // モード3
+        advance(&mut ppu, &mut mmu, 456 * 2 - 80);
+        assert_eq!(mmu.read_byte(0xFF44), 2);
+        assert_eq!(mmu.read_byte(0xFF41), 0x4A); // モード2
+        advance(&mut ppu, &mut mmu, 456 * 142);
+        assert_eq!(mmu.read_byte(0xFF44), 144);
+        assert_eq!(mmu.read_byte(0xFF41) & 0x03, 1); // VBlank
+    }
+
+    #[test]
+    fn scanline_ranges_and_wrap() {
+        assert!(Scanline(143).is_visible() && !Scanline(143).is_vblank());
+        assert!(Scanline(144).is_vblank() && !Scanline(144).is_visible());
+        assert!(Scanline(153).is_vblank());
+        assert_eq!(Scanline(0).next(), Scanline(1));
+        assert_eq!(Scanline(143).next(), Scanline(144));
+        assert_eq!(Scanline(153).next(), Scanline(0));
+    }
+
+    #[test]
+    fn current_scanline_follows_ly() {
+        let (mut ppu, mut mmu) = setup();
+        assert_eq!(ppu.current_scanline(), Scanline(0));
+        advance(&mut ppu, &mut mmu, 456 * 144);
+        assert_eq!(ppu.current_scanline(), Scanline(144));
+        assert!(ppu.current_scanline().is_vblank());
+        assert_eq!(ppu.current_scanline().0, ppu.ly());
+    }
+
+    #[test]
+    fn vblank_from_line_144_and_wraps_at_154() {
+        let (mut ppu, mut mmu) = setup();
+        advance(&mut ppu, &mut mmu, 456 * 144 - 1);
+        assert_eq!((ppu.mode(), ppu.ly()), (PpuMode::HBlank, 143));
+        assert!(!ppu.in_vblank());
+        ppu.step(&mut mmu, 1);
+        assert_eq!((ppu.mode(), ppu.ly()), (PpuMode::VBlank, 144));
+        assert!(ppu.in_vblank());
+        advance(&mut ppu, &mut mmu, 456 * 10 - 1);
+        assert_eq!((ppu.mode(), ppu.ly()), (PpuMode::VBlank, 153));
+        ppu.step(&mut mmu, 1);
+        assert_eq!((ppu.mode(), ppu.ly(), ppu.dot()), (PpuMode::OamScan, 0, 0));
+        assert!(!ppu.in_vblank());
+    }
+
+    #[test]
+    fn ly_wraps_after_154_lines() {
+        let (mut ppu, mut mmu) = setup();
+        advance(&mut ppu, &mut mmu, 455);
+        assert_eq!((ppu.ly(), ppu.dot()), (0, 455));
+        ppu.step(&mut mmu, 1);
+        assert_eq!((ppu.ly(), ppu.dot()), (1, 0));
+        advance(&mut ppu, &mut mmu, 456 * 153);
+        assert_eq!((ppu.ly(), ppu.dot()), (0, 0));
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn ppu_serde_round_trip() {
+        let (mut ppu, mut mmu) = setup();
+        mmu.write_byte(0xFE00, 0x10); // スプライト0: y = 16 (ライン0)
+        advance(&mut ppu, &mut mmu, 456 * 3 + 100);
+
+        let json = serde_json::to_string(&ppu).unwrap();
+        let loaded: PPU = serde_json::from_str(&json).unwrap();
+        assert_eq!(loaded.mode(), ppu.mode());
+        assert_eq!((loaded.ly, loaded.dot), (ppu.ly, ppu.dot));
+        assert_eq!(loaded.line_sprites(), ppu.line_sprites());
+    }
+
+    #[test]
+    fn tile_map_reads_both_maps() {
+        let (ppu, mut mmu) = setup();
+        mmu.write_byte(0x9800, 0x01);
+        mmu.write_byte(0x9821, 0x02); // 行1, 列1
+        mmu.write_byte(0x9BFF, 0x03);
+        mmu.write_byte(0x9FFF, 0x04);
+
+        let map = ppu.get_tile_map(&mmu, 0);
+        assert_eq!(map.base, 0x9800);
+        assert_eq!((map.tiles[0][0], map.tiles[1][1]), (0x01, 0x02));
+        assert_eq!(map.tiles[31][31], 0x03);
+        assert_eq!(map.attributes, [[0; 32]; 32]);
+
+        let map = ppu.get_tile_map(&mmu, 1);
+        assert_eq!(map.base, 0x9C00);
+        assert_eq!((map.tiles[0][0], map.tiles[31][31]), (0x00, 0x04));
+    }
+
+    #[test]
+    fn tile_map_reads_cgb_attributes_from_bank1() {
+        let mut rom = vec![0x00; 0x8000];
+        rom[0x0143] = 0x80; // CGB対応
+        let (ppu, mut mmu) = (PPU::new(), MMU::new(rom));
+        mmu.write_byte(0x9821, 0x02);
+        mmu.write_byte(0xFF4F, 0x01);
+        mmu.write_byte(0x9821, 0x28); // 行1, 列1 の属性
+
+        let map = ppu.get_tile_map(&mmu, 0);
+        assert_eq!(map.tiles[1][1], 0x02);
+        assert_eq!(map.attributes[1][1], 0x28);
+        assert_eq!(map.attributes[0][0], 0x00);
+    }
+
+    #[test]
+    fn sprite_flags_decode_attribute_bits() {
+        let flags = SpriteFlags(0xF0);
+        assert_eq!(flags.palette(), 1);
+        assert!(flags.x_flip() && flags.y_flip() && flags.bg_priority());
+        assert_eq!((flags.vram_bank(), flags.cgb_palette()), (0, 0));
+
+        let flags = SpriteFlags(0x0D);
+        assert_eq!(flags.palette(), 0);
+        assert!(!flags.x_flip() && !flags.y_flip() && !flags.bg_priority());
+        assert_eq!((flags.vram_bank(), flags.cgb_palette()), (1, 5));
+    }
+
+    #[test]
+    fn mix_picks_sprite_or_bg() {
+        let front = SpriteFlags(0x00);
+        let behind = SpriteFlags(0x80); // BG優先
+        for colour in 0..4 {
+            let bg = Pixel::bg(colour, 0);
+            // 色0 のスプライトは常に透明
+            assert_eq!(mix(bg, Pixel::sprite(0, front, false)), bg);
+            assert_eq!(mix(bg, Pixel::sprite(0, behind, false)), bg);
+            let sprite = Pixel::sprite(2, front, false);
+            assert_eq!(mix(bg, sprite), sprite);
+        }
+        let sprite = Pixel::sprite(3, behind, false);
+        assert_eq!(mix(Pixel::bg(0, 0), sprite), sprite); // 背景色0 の上には出る
+        for colour in 1..4 {
+            let bg = Pixel::bg(colour, 0);
+            assert_eq!(mix(bg, sprite), bg);
+        }
+    }
+
+    #[test]
+    fn sprite_pixel_takes_palette_from_flags() {
+        let flags = SpriteFlags(0x9D); // BG優先, OBP1, CGBパレット5
+        let dmg = Pixel::sprite(1, flags, false);
+        assert_eq!(
+            (dmg.palette, dmg.is_sprite, dmg.bg_priority),
+            (1, true, true)
+        );
+        assert_eq!(Pixel::sprite(1, flags, true).palette, 5);
+        assert!(!Pixel::bg(1, 0).is_sprite);
+    }
+
+    #[test]
+    fn sprites_decode_oam_entries() {
+        let (ppu, mut mmu) = setup();
+        for (i, value) in [0x10, 0x08, 0x42, 0x20].into_iter().enumerate() {
+            mmu.write_byte(0xFE04 + i as u16, value); // エントリ1
+        }
+        mmu.write_byte(0xFE9C, 0xA0); // エントリ39の y
+        let sprites = ppu.sprites(&mmu);
+        assert_eq!(sprites[0], Sprite::default());
+        assert_eq!(
+            sprites[1],
+            Sprite {
+                y: 0x10,
+                x: 0x08,
+                tile_index: 0x42,
+                flags: SpriteFlags(0x20),
+            }
+        );
+        assert_eq!(sprites[39].y, 0xA0);
+    }
+
+    // OAM の index 番目のエントリを書き込む
+    fn write_sprite(mmu: &mut MMU, index: u16, y: u8, x: u8) {
+        mmu.write_byte(0xFE00 + index * 4, y);
+        mmu.write_byte(0xFE00 + index * 4 + 1, x);
+        mmu.write_byte(0xFE00 + index * 4 + 2, index as u8); // タイル番号で識別する
+    }
+
+    #[test]
+    fn scan_keeps_first_ten_sprites_in_oam_order() {
+        let (ppu, mut mmu) = setup();
+        write_sprite(&mut mmu, 0, 0x00, 0x50); // 画面外 (y = -16)
+        for i in 1..=11 {
+            write_sprite(&mut mmu, i, 0x10 + 4, 0xA0 - i as u8); // ライン 4-11 に掛かる
+        }
+        let line = ppu.scan_sprites_for_line(&mmu, 4);
+        let tiles: Vec<u8> = line.iter().map(|s| s.unwrap().tile_index).collect();
+        assert_eq!(tiles, (1..=10).collect::<Vec<u8>>()); // 11番目は落ちる
+        assert!(
+            ppu.scan_sprites_for_line(&mmu, 3)
+                .iter()
+                .all(|s| s.is_none())
+        );
+        assert!(
+            ppu.scan_sprites_for_line(&mmu, 12)
+                .iter()
+                .all(|s| s.is_none())
+        );
+    }
+
+    #[test]
+    fn scan_uses_lcdc_sprite_height() {
+        let (ppu, mut mmu) = setup();
+        write_sprite(&mut mmu, 0, 0x10, 0x08);
+        assert!(ppu.scan_sprites_for_line(&mmu, 7)[0].is_some());
+        assert!(ppu.scan_sprites_for_line(&mmu, 8)[0].is_none());
+        mmu.write_byte(0xFF40, 0x04); // 8x16
+        assert!(ppu.scan_sprites_for_line(&mmu, 15)[0].is_some());
+        assert!(ppu.scan_sprites_for_line(&mmu, 16)[0].is_none());
+    }
+
+    #[test]
+    fn step_scans_sprites_at_line_start() {
+        let (mut ppu, mut mmu) = setup();
+        write_sprite(&mut mmu, 5, 0x10 + 1, 0x08); // ライン 1-8
+        advance(&mut ppu, &mut mmu, 455);
+        assert!(ppu.line_sprites()[0].is_none());
+        ppu.step(&mut mmu, 1);
+        assert_eq!(ppu.line_sprites()[0].unwrap().tile_index, 5);
+    }
+
+    // MMU なしで tick を回し、起きたイベントを集める
+    fn tick_events(ppu: &mut PPU, dots: u32) -> PpuEvents {
+        let mut events = PpuEvents::default();
+        for _ in 0..dots {
+            events.insert(ppu.tick());
+        }
+        events
+    }
+
+    #[test]
+    fn tick_reports_mode_events_without_mmu() {
+        let mut ppu = PPU::new();
+        assert_eq!(tick_events(&mut ppu, 251), PpuEvents::default());
+        assert_eq!(ppu.tick(), PpuEvents::STAT_MODE0);
+        assert_eq!(tick_events(&mut ppu, 203), PpuEvents::default());
+        assert_eq!(ppu.tick(), PpuEvents::STAT_MODE2);
+        assert_eq!(ppu.ly(), 1);
+    }
+
+    #[test]
+    fn tick_reports_vblank_once_per_frame() {
+        let mut ppu = PPU::new();
+        ppu.lyc = 0xFF; // LYC一致を起こさない
+        tick_events(&mut ppu, 456 * 144 - 1);
+        let events = ppu.tick();
+        assert!(events.contains(PpuEvents::VBLANK));
+        assert!(!events.contains(PpuEvents::STAT_MODE2));
+        assert!(!tick_events(&mut ppu, 456 * 154 - 1).contains(PpuEvents::VBLANK));
+        assert!(ppu.tick().contains(PpuEvents::VBLANK));
+    }
+
+    #[test]
+    fn tick_reports_lyc_match_at_line_start() {
+        let mut ppu = PPU::new();
+        ppu.lyc = 2;
+        assert!(!tick_events(&mut ppu, 456 * 2 - 1).contains(PpuEvents::STAT_LYC));
+        let events = ppu.tick();
+        assert!(events.contains(PpuEvents::STAT_LYC));
+        assert!(events.contains(PpuEvents::STAT_MODE2));
+        assert!(!tick_events(&mut ppu, 455).contains(PpuEvents::STAT_LYC));
+    }
+
+    #[test]
+    fn step_turns_events_into_interrupts() {
+        // VBlank: LY 144 に入ったら要求
+        let (mut ppu, mut mmu) = setup();
+        advance(&mut ppu, &mut mmu, 456 * 144 - 1);
+        assert!(!mmu.interrupt_flag().vblank());
+        ppu.step(&mut mmu, 1);
+        assert!(mmu.interrupt_flag().vblank());
+
+        // モード2 (bit5): 次のライン開始で要求
+        let (mut ppu, mut mmu) = setup();
+        mmu.write_byte(0xFF41, 0x20);
+        advance(&mut ppu, &mut mmu, 455);
+        assert!(!mmu.interrupt_flag().lcd_stat());
+        ppu.step(&mut mmu, 1);
+        assert!(mmu.interrupt_flag().lcd_stat());
+
+        // LYC一致 (bit6): LY が 0xFF45 と同じになったら要求
+        let (mut ppu, mut mmu) = setup();
+        mmu.write_byte(0xFF41, 0x40);
+        mmu.write_byte(0xFF45, 3);
+        advance(&mut ppu, &mut mmu, 456 * 3 - 1);
+        assert!(!mmu.interrupt_flag().lcd_stat());
+        ppu.step(&mut mmu, 1);
+        assert!(mmu.interrupt_flag().lcd_stat());
+    }
+
+    #[test]
+    fn hblank_stat_interrupt_at_dot_252() {
+        let (mut ppu, mut mmu) = setup();
+        advance(&mut ppu, &mut mmu, 300);
+        assert!(!mmu.interrupt_flag().lcd_stat()); // STAT bit3 が無効
+
+        mmu.write_byte(0xFF41, 0x08);
+        advance(&mut ppu, &mut mmu, 456 - 300 + 251);
+        assert!(!mmu.interrupt_flag().lcd_stat());
+        ppu.step(&mut mmu, 1);
+        assert_eq!(ppu.mode(), PpuMode::HBlank);
+        assert!(mmu.interrupt_flag().lcd_stat());
+
+        // HBlank 中は再要求しない
+        mmu.write_byte(0xFF0F, 0x00);
+        advance(&mut ppu, &mut mmu, 200);
+        assert!(!mmu.interrupt_flag().lcd_stat());
+    }
+}
@@ -0,0 +1,326 @@
+use crate::serial::SerialLink;
+
+// ポケットプリンタ (シリアル通信の相手)
+// パケット: 0x88 0x33, コマンド, 圧縮フラグ, データ長 (2バイト LE), データ,
+//           チェックサム (2バイト LE, コマンドからデータまでの和), 0x00 0x00
+// 最後の2バイトに対してプリンタは 0x81 (接続確認) とステータスを返す
+
+const TILES_PER_ROW: usize = 20; // 160px
+const BYTES_PER_TILE: usize = 16; // 8x8, 2bpp
+
+// ステータスバイト
+const STATUS_CHECKSUM_ERROR: u8 = 0x01;
+const STATUS_UNPROCESSED: u8 = 0x08; // 印刷していないデータがある
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum PacketState {
+    Magic1,
+    Magic2,
+    Command,
+    Compression,
+    LengthLow,
+    LengthHigh,
+    Data,
+    ChecksumLow,
+    ChecksumHigh,
+    KeepAlive,
+    Status,
+}
+
+pub struct Printer {
+    state: PacketState,
+    command: u8,
+    compressed: bool,
+    length: u16,
+    data: Vec<u8>,
+    checksum: u16,          // 受信したバイトから計算した値
+    received_checksum: u16, // パケットに書かれていた値
+    status: u8,
+    tiles: Vec<u8>, // 受信済みのタイルデータ (伸長後)
+    on_image_ready: Box<dyn FnMut(Vec<Vec<u8>>)>,
+}
+
+impl Printer {
+    // 印刷コマンドを受け取るたびに on_image_ready に画像を渡す
+    // 画像は 160px 幅の行の並びで、各ピクセルは印刷パレット適用後の濃さ (0-3)
+    pub fn new(on_image_ready: impl FnMut(Vec<Vec<u8>>) + 'static) -> Self {
+        Self {
+            state: PacketState::Magic1,
+            command: 0,
+            compressed: false,
+            length: 0,
+            data: Vec::new(),
+            checksum: 0,
+            received_checksum: 0,
+            status: 0,
+            tiles: Vec::new(),
+            on_image_ready: Box::new(on_image_ready),
+        }
+    }
+
+    // チェックサムの対象 (コマンドからデータまで) を受け取る
+    fn add_to_checksum(&mut self, byte: u8) {
+        self.checksum = self.checksum.wrapping_add(byte as u16);
+    }
+
+    // チェックサムまで受け取ったパケットを処理する
+    fn process_packet(&mut self) {
+        if self.checksum != self.received_checksum {
+            self.status |= STATUS_CHECKSUM_ERROR;
+            return;
+        }
+        self.status &= !STATUS_CHECKSUM_ERROR;
+        match self.command {
+            0x01 => {
+                // 初期化
+                self.tiles.clear();
+                self.status = 0;
+            }
+            0x02 => {
+                // 印刷 (データ: 枚数, 余白, パレット, 濃さ)
+                let palette = self.data.get(2).copied().unwrap_or(0xE4);
+                let image = self.decode_image(palette);
+                self.tiles.clear();
+                self.status &= !STATUS_UNPROCESSED;
+                (self.on_image_ready)(image);
+            }
+            0x04 => {
+                // 画像データ (長さ0 はデータの終わり)
+                if self.compressed {
+                    decompress(&self.data, &mut self.tiles);
+                } else {
+                    self.tiles.extend_from_slice(&self.data);
+                }
+                if !self.tiles.is_empty() {
+                    self.status |= STATUS_UNPROCESSED;
+                }
+            }
+            _ => {} // 0x0F: ステータスの確認のみ
+        }
+    }
+
+    // タイルを 20 枚ずつ横に並べた画像に変換する (パレット 0 は標準の 0xE4 扱い)
+    fn decode_image(&self, palette: u8) -> Vec<Vec<u8>> {
+        let palette = if palette == 0 { 0xE4 } else { palette };
+        let row_bytes = TILES_PER_ROW * BYTES_PER_TILE;
+        let mut image = Vec::new();
+        for tile_row in self.tiles.chunks_exact(row_bytes) {
+            for y in 0..8 {
+                let mut line = Vec::with_capacity(TILES_PER_ROW * 8);
+                for tile in tile_row.chunks_exact(BYTES_PER_TILE) {
+                    let low = tile[y * 2];
+                    let high = tile[y * 2 + 1];
+                    for bit in (0..8).rev() {
+                        let colour = (((high >> bit) & 1) << 1) | ((low >> bit) & 1);
+                        line.push((palette >> (colour * 2)) & 0x03);
+                    }
+                }
+                image.push(line);
+            }
+        }
+        image
+    }
+}
+
+impl SerialLink for Printer {
+    // パケットの途中は 0x00 を返す
+    fn transfer(&mut self, byte: u8) -> u8 {
+        match self.state {
+            PacketState::Magic1 => {
+                if byte == 0x88 {
+                    self.state = PacketState::Magic2;
+                }
+            }
+            PacketState::Magic2 => {
+                self.state = if byte == 0x33 {
+                    PacketState::Command
+                } else {
+                    PacketState::Magic1
+                };
+            }
+            PacketState::Command => {
+                self.command = byte;
+                self.checksum = 0;
+                self.add_to_checksum(byte);
+                self.state = PacketState::Compression;
+            }
+            PacketState::Compression => {
+                self.compressed = byte & 0x01 != 0;
+                self.add_to_checksum(byte);
+                self.state = PacketState::LengthLow;
+            }
+            PacketState::LengthLow => {
+                self.length = byte as u16;
+                self.add_to_checksum(byte);
+                self.state = PacketState::LengthHigh;
+            }
+            PacketState::LengthHigh => {
+                self.length |= (byte as u16) << 8;
+                self.add_to_checksum(byte);
+                self.data.clear();
+                self.state = if self.length == 0 {
+                    PacketState::ChecksumLow
+                } else {
+                    PacketState::Data
+                };
+            }
+            PacketState::Data => {
+                self.data.push(byte);
+                self.add_to_checksum(byte);
+                if self.data.len() == self.length as usize {
+                    self.state = PacketState::ChecksumLow;
+                }
+            }
+            PacketState::ChecksumLow => {
+                self.received_checksum = byte as u16;
+                self.state = PacketState::ChecksumHigh;
+            }
+            PacketState::ChecksumHigh => {
+                self.received_checksum |= (byte as u16) << 8;
+                self.process_packet();
+                self.state = PacketState::KeepAlive;
+            }
+            PacketState::KeepAlive => {
+                self.state = PacketState::Status;
+                return 0x81;
+            }
+            PacketState::Status => {
+                self.state = PacketState::Magic1;
+                return self.status;
+            }
+        }
+        0x00
+    }
+}
+
+// 画像データのランレングス圧縮を伸長する
+// bit7 = 1: 次の1バイトを (下位7bit + 2) 回, bit7 = 0: 続く (値 + 1) バイトをそのまま
+fn decompress(data: &[u8], out: &mut Vec<u8>) {
+    let mut i = 0;
+    while i < data.len() {
+        let control = data[i];
+        i += 1;
+        if control & 0x80 != 0 {
+            let count = (control & 0x7F) as usize + 2;
+            if let Some(&byte) = data.get(i) {
+                out.extend(std::iter::repeat_n(byte, count));
+            }
+            i += 1;
+        } else {
+            let end = (i + control as usize + 1).min(data.len());
+            out.extend_from_slice(&data[i..end]);
+            i = end;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    type Images = Rc<RefCell<Vec<Vec<Vec<u8>>>>>; // 受け取った画像
+
+    fn setup() -> (Printer, Images) {
+        let images = Rc::new(RefCell::new(Vec::new()));
+        let sink = Rc::clone(&images);
+        let printer = Printer::new(move |image| sink.borrow_mut().push(image));
+        (printer, images)
+    }
+
+    // パケットを送り、最後の2バイトへの応答 (接続確認, ステータス) を返す
+    fn send_packet(printer: &mut Printer, command: u8, compression: u8, data: &[u8]) -> (u8, u8) {
+        let length = data.len() as u16;
+        let mut body = vec![command, compression, length as u8, (length >> 8) as u8];
+        body.extend_from_slice(data);
+        let checksum = body.iter().fold(0u16, |sum, &b| sum.wrapping_add(b as u16));
+
+        let mut packet = vec![0x88, 0x33];
+        packet.extend_from_slice(&body);
+        packet.extend_from_slice(&[checksum as u8, (checksum >> 8) as u8]);
+        for byte in packet {
+            assert_eq!(printer.transfer(byte), 0x00);
+        }
+        (printer.transfer(0x00), printer.transfer(0x00))
+    }
+
+    // 1行分 (20タイル) のタイルデータ: 全ピクセルが色番号 colour
+    fn tile_row(colour: u8) -> Vec<u8> {
+        let low = if colour & 0x01 != 0 { 0xFF } else { 0x00 };
+        let high = if colour & 0x02 != 0 { 0xFF } else { 0x00 };
+        [low, high].repeat(8 * TILES_PER_ROW)
+    }
+
+    #[test]
+    fn prints_received_strips() {
+        let (mut printer, images) = setup();
+        assert_eq!(send_packet(&mut printer, 0x01, 0, &[]), (0x81, 0x00));
+
+        let mut strip = tile_row(1);
+        strip.extend(tile_row(3));
+        assert_eq!(send_packet(&mut printer, 0x04, 0, &strip), (0x81, 0x08));
+        send_packet(&mut printer, 0x04, 0, &[]); // データの終わり
+        assert!(images.borrow().is_empty());
+
+        // 印刷: 1枚, 余白なし, パレット 0xE4 (色番号そのまま)
+        assert_eq!(
+            send_packet(&mut printer, 0x02, 0, &[0x01, 0x00, 0xE4, 0x40]),
+            (0x81, 0x00)
+        );
+        let images = images.borrow();
+        assert_eq!(images.len(), 1);
+        let image = &images[0];
+        assert_eq!(image.len(), 16);
+        assert!(image.iter().all(|line| line.len() == 160));
+        assert!(image[..8].iter().all(|line| line.iter().all(|&p| p == 1)));
+        assert!(image[8..].iter().all(|line| line.iter().all(|&p| p == 3)));
+    }
+
+    #[test]
+    fn print_applies_palette() {
+        let (mut printer, images) = setup();
+        send_packet(&mut printer, 0x04, 0, &tile_row(1));
+        send_packet(&mut printer, 0x02, 0, &[0x01, 0x00, 0x1B, 0x40]); // 色番号を反転
+        assert_eq!(images.borrow()[0][0][0], 2);
+    }
+
+    #[test]
+    fn compressed_data_is_expanded() {
+        let (mut printer, images) = setup();
+        // 色番号3 の1行 = 0xFF が 320 バイト (129 + 129 + 62 回の繰り返し)
+        let compressed = [0xFF, 0xFF, 0xFF, 0xFF, 0xBC, 0xFF];
+        send_packet(&mut printer, 0x04, 1, &compressed);
+        send_packet(&mut printer, 0x02, 0, &[0x01, 0x00, 0xE4, 0x40]);
+        assert_eq!(images.borrow()[0].len(), 8);
+        assert!(images.borrow()[0].iter().all(|line| line == &[3; 160]));
+
+        let mut out = Vec::new();
+        decompress(&[0x82, 0xAA, 0x01, 0x12, 0x34], &mut out);
+        assert_eq!(out, [0xAA, 0xAA, 0xAA, 0xAA, 0x12, 0x34]);
+    }
+
+    #[test]
+    fn checksum_error_is_reported_and_data_dropped() {
+        let (mut printer, images) = setup();
+        for byte in [0x88, 0x33, 0x04, 0x00, 0x01, 0x00, 0xAA, 0x00, 0x00] {
+            printer.transfer(byte); // チェックサムが合わない
+        }
+        assert_eq!(printer.transfer(0x00), 0x81);
+        assert_eq!(printer.transfer(0x00), STATUS_CHECKSUM_ERROR);
+
+        // 次の正しいパケットでエラーは消える
+        assert_eq!(send_packet(&mut printer, 0x0F, 0, &[]), (0x81, 0x00));
+        send_packet(&mut printer, 0x02, 0, &[0x01, 0x00, 0xE4, 0x40]);
+        assert!(images.borrow()[0].is_empty());
+    }
+
+    #[test]
+    fn bytes_before_sync_are_ignored() {
+        let (mut printer, _) = setup();
+        assert_eq!(printer.transfer(0x12), 0x00);
+        assert_eq!(printer.transfer(0x88), 0x00);
+        assert_eq!(printer.transfer(0x00), 0x00); // 0x33 でないのでやり直し
+        assert_eq!(send_packet(&mut printer, 0x0F, 0, &[]), (0x81, 0x00));
+    }
+}
@@ -0,0 +1,6 @@
+// シリアルポート (SB/SC) の通信相手
+// 通信ケーブルの先の機器 (プリンタなど) を表す
+pub trait SerialLink {
+    // 1バイト送り、相手から同時に受け取った1バイトを返す
+    fn transfer(&mut self, byte: u8) -> u8;
+}